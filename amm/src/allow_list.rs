@@ -0,0 +1,261 @@
+use near_sdk::{
+    env, ext_contract, json_types::U128, log, near_bindgen, serde_json, AccountId, Promise,
+    PromiseResult,
+};
+use shared::OutcomeId;
+
+use crate::consts::*;
+use crate::storage::*;
+
+#[ext_contract(ext_self)]
+trait Callbacks {
+    fn on_allow_list_registry_is_allowed_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> U128;
+    fn on_allow_list_registry_is_allowed_with_near_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    );
+}
+
+#[ext_contract(ext_allow_list_registry)]
+trait AllowListRegistry {
+    fn is_allowed(&self, account_id: AccountId) -> bool;
+}
+
+#[near_bindgen]
+impl Market {
+    /**
+     * Lets accounts participate in a restricted market
+     *
+     * @notice only by the market creator or the DAO
+     */
+    pub fn add_to_allow_list(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_only_management();
+        self.assert_is_restricted();
+
+        if let Some(allow_list) = &mut self.allow_list {
+            allow_list.accounts.extend(account_ids);
+        }
+    }
+
+    /**
+     * Removes accounts from a restricted market, their current balances are kept
+     *
+     * @notice only by the market creator or the DAO
+     */
+    pub fn remove_from_allow_list(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_only_management();
+        self.assert_is_restricted();
+
+        if let Some(allow_list) = &mut self.allow_list {
+            for account_id in account_ids.iter() {
+                allow_list.accounts.remove(account_id);
+            }
+        }
+    }
+
+    /**
+     * Sets or unsets the external registry queried for accounts not listed in the allow list
+     *
+     * @notice only by the market creator or the DAO
+     */
+    pub fn set_allow_list_registry(&mut self, registry_account_id: Option<AccountId>) {
+        self.assert_only_management();
+        self.assert_is_restricted();
+
+        if let Some(allow_list) = &mut self.allow_list {
+            allow_list.registry_account_id = registry_account_id;
+        }
+    }
+
+    /**
+     * Buys on behalf of sender_id if the registry allowed it, the account is then listed in the allow list
+     *
+     * @returns the amount of collateral to refund, all of it if the account is not allowed
+     */
+    #[private]
+    pub fn on_allow_list_registry_is_allowed_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> U128 {
+        let is_allowed: bool = match env::promise_result(0) {
+            PromiseResult::Successful(result) => serde_json::from_slice(&result).unwrap_or(false),
+            _ => false,
+        };
+
+        log!(
+            "on_allow_list_registry_is_allowed_callback: {}, is_allowed: {}",
+            sender_id,
+            is_allowed
+        );
+
+        if !is_allowed {
+            return U128::from(amount);
+        }
+
+        if let Some(allow_list) = &mut self.allow_list {
+            allow_list.accounts.insert(&sender_id);
+        }
+
+        self.buy(sender_id, amount, BuyArgs { outcome_id });
+
+        U128::from(0)
+    }
+
+    /**
+     * Wraps the NEAR deposit and buys on behalf of sender_id if the registry allowed it, see fn buy_with_near
+     * The deposit is sent back if the account is not allowed
+     */
+    #[private]
+    #[payable]
+    pub fn on_allow_list_registry_is_allowed_with_near_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) {
+        let is_allowed: bool = match env::promise_result(0) {
+            PromiseResult::Successful(result) => serde_json::from_slice(&result).unwrap_or(false),
+            _ => false,
+        };
+
+        log!(
+            "on_allow_list_registry_is_allowed_with_near_callback: {}, is_allowed: {}",
+            sender_id,
+            is_allowed
+        );
+
+        if !is_allowed {
+            Promise::new(sender_id).transfer(amount);
+            return;
+        }
+
+        if let Some(allow_list) = &mut self.allow_list {
+            allow_list.accounts.insert(&sender_id);
+        }
+
+        self.internal_buy_with_near(sender_id, amount, outcome_id);
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.allow_list.is_some()
+    }
+
+    pub fn is_allowed(&self, account_id: AccountId) -> bool {
+        match &self.allow_list {
+            Some(allow_list) => allow_list.accounts.contains(&account_id),
+            None => true,
+        }
+    }
+
+    pub fn get_allow_list(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        match &self.allow_list {
+            Some(allow_list) => {
+                let elements = allow_list.accounts.as_vector();
+
+                (from_index..std::cmp::min(from_index.saturating_add(limit), elements.len()))
+                    .filter_map(|index| elements.get(index))
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    pub fn get_allow_list_count(&self) -> u64 {
+        match &self.allow_list {
+            Some(allow_list) => allow_list.accounts.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_allow_list_registry(&self) -> Option<AccountId> {
+        match &self.allow_list {
+            Some(allow_list) => allow_list.registry_account_id.clone(),
+            None => None,
+        }
+    }
+}
+
+impl Market {
+    /**
+     * Queries the external registry for accounts not listed in the allow list of a restricted market
+     *
+     * @returns None if the account can buy right away
+     */
+    pub fn internal_allow_list_registry_check(
+        &self,
+        sender_id: &AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> Option<Promise> {
+        if self.is_allowed(sender_id.clone()) {
+            return None;
+        }
+
+        match self.get_allow_list_registry() {
+            Some(registry_account_id) => {
+                let is_allowed_promise = ext_allow_list_registry::ext(registry_account_id)
+                    .with_attached_deposit(0)
+                    .with_static_gas(GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED)
+                    .is_allowed(sender_id.clone());
+
+                let is_allowed_callback_promise = ext_self::ext(env::current_account_id())
+                    .with_attached_deposit(0)
+                    .with_static_gas(GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED_CALLBACK)
+                    .on_allow_list_registry_is_allowed_callback(
+                        sender_id.clone(),
+                        amount,
+                        outcome_id,
+                    );
+
+                Some(is_allowed_promise.then(is_allowed_callback_promise))
+            }
+            None => env::panic_str("ERR_ACCOUNT_IS_NOT_ALLOWED"),
+        }
+    }
+
+    /**
+     * fn internal_allow_list_registry_check for fn buy_with_near, the NEAR deposit is carried to the callback
+     *
+     * @returns None if the account can buy right away
+     */
+    pub fn internal_allow_list_registry_check_with_near(
+        &self,
+        sender_id: &AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> Option<Promise> {
+        if self.is_allowed(sender_id.clone()) {
+            return None;
+        }
+
+        match self.get_allow_list_registry() {
+            Some(registry_account_id) => {
+                let is_allowed_promise = ext_allow_list_registry::ext(registry_account_id)
+                    .with_attached_deposit(0)
+                    .with_static_gas(GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED)
+                    .is_allowed(sender_id.clone());
+
+                let is_allowed_callback_promise = ext_self::ext(env::current_account_id())
+                    .with_attached_deposit(amount)
+                    .with_static_gas(GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED_WITH_NEAR_CALLBACK)
+                    .on_allow_list_registry_is_allowed_with_near_callback(
+                        sender_id.clone(),
+                        amount,
+                        outcome_id,
+                    );
+
+                Some(is_allowed_promise.then(is_allowed_callback_promise))
+            }
+            None => env::panic_str("ERR_ACCOUNT_IS_NOT_ALLOWED"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use near_sdk::{env, log, near_bindgen, AccountId, PromiseResult};
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::{env, log, near_bindgen, serde_json, AccountId, Promise, PromiseResult};
 use num_format::ToFormattedString;
 use shared::OutcomeId;
 
@@ -6,6 +7,11 @@ use crate::{storage::*, FORMATTED_STRING_LOCALE};
 
 #[near_bindgen]
 impl Market {
+    /**
+     * Restores the seller balances if the sell transfer failed, see fn internal_sell
+     *
+     * @returns the amount paid
+     */
     #[private]
     pub fn on_ft_transfer_callback(
         &mut self,
@@ -21,15 +27,219 @@ impl Market {
                     amount_payable.to_formatted_string(&FORMATTED_STRING_LOCALE)
                 );
 
-                let mut outcome_token = self.get_outcome_token(outcome_id);
-                outcome_token.burn(&payee, amount);
+                self.internal_update_position_on_sell(&payee, outcome_id, amount_payable);
 
-                self.update_ct_balance(self.collateral_token.balance - amount_payable);
-                self.outcome_tokens.insert(&outcome_id, &outcome_token);
+                amount_payable.to_string()
+            }
+            // On error, the collateral is still held by the market
+            _ => {
+                self.internal_revert_sell(&payee, outcome_id, amount, amount_payable);
 
-                return amount_payable.to_string();
+                0.to_string()
             }
-            _ => env::panic_str("ERR_ON_FT_TRANSFER_CALLBACK"),
         }
     }
+
+    #[private]
+    pub fn on_near_deposit_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> WrappedBalance {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {
+                log!(
+                    "on_near_deposit_callback.amount: {}",
+                    amount.to_formatted_string(&FORMATTED_STRING_LOCALE)
+                );
+
+                // The market may have changed since fn buy_with_near, the wrapped deposit is sent back instead of lost
+                let buy_error = if self.is_allowed(sender_id.clone()) {
+                    self.internal_get_buy_error(&sender_id, amount, outcome_id)
+                } else {
+                    Some("ERR_ACCOUNT_IS_NOT_ALLOWED")
+                };
+
+                if let Some(error) = buy_error {
+                    log!(
+                        "on_near_deposit_callback: {}, refunding {}",
+                        error,
+                        sender_id
+                    );
+
+                    self.internal_refund_buy_with_near(sender_id, amount);
+
+                    return 0;
+                }
+
+                self.buy(sender_id, amount, BuyArgs { outcome_id })
+            }
+            // On error, the attached deposit was returned to this contract
+            _ => {
+                Promise::new(sender_id).transfer(amount);
+
+                0
+            }
+        }
+    }
+
+    /**
+     * Sends the unwrapped refund, or logs it for recovery if the wNEAR could not be unwrapped
+     *
+     * @notice the wNEAR of a failed refund stays with the market, outside its collateral balance
+     */
+    #[private]
+    pub fn on_buy_with_near_refund_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+    ) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {
+                Promise::new(sender_id).transfer(amount);
+            }
+            _ => {
+                log!(
+                    "EVENT_JSON:{}",
+                    serde_json::json!({
+                        "standard": "pulsemarkets",
+                        "version": "1.0.0",
+                        "event": "buy_with_near_refund_failure",
+                        "data": [{
+                            "sender_id": sender_id,
+                            "amount": amount.to_string(),
+                        }]
+                    })
+                );
+            }
+        }
+    }
+
+    /**
+     * Sends the unwrapped NEAR, or restores the seller balances if the wNEAR could not be unwrapped
+     *
+     * @returns the amount paid
+     */
+    #[private]
+    pub fn on_near_withdraw_callback(
+        &mut self,
+        amount: WrappedBalance,
+        payee: AccountId,
+        outcome_id: OutcomeId,
+        amount_payable: WrappedBalance,
+    ) -> String {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {
+                log!(
+                    "on_near_withdraw_callback.amount_payable: {}",
+                    amount_payable.to_formatted_string(&FORMATTED_STRING_LOCALE)
+                );
+
+                self.internal_update_position_on_sell(&payee, outcome_id, amount_payable);
+
+                Promise::new(payee).transfer(amount_payable);
+
+                amount_payable.to_string()
+            }
+            // On error, the wNEAR is still held by the market
+            _ => {
+                self.internal_revert_sell(&payee, outcome_id, amount, amount_payable);
+
+                0.to_string()
+            }
+        }
+    }
+
+    /**
+     * Restores the holder balances if the claim transfer failed, so they may be claimed again
+     *
+     * @returns the amount paid
+     */
+    #[private]
+    pub fn on_claim_all_callback(
+        &mut self,
+        payee: AccountId,
+        receiver_id: AccountId,
+        claims: Vec<Claim>,
+        amount_payable: WrappedBalance,
+    ) -> String {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {
+                log!(
+                    "on_claim_all_callback.amount_payable: {}",
+                    amount_payable.to_formatted_string(&FORMATTED_STRING_LOCALE)
+                );
+
+                for claim in claims {
+                    self.internal_update_position_on_sell(
+                        &payee,
+                        claim.outcome_id,
+                        claim.amount_payable,
+                    );
+                }
+
+                if self.is_collateral_token_wrap_near() {
+                    Promise::new(receiver_id).transfer(amount_payable);
+                }
+
+                amount_payable.to_string()
+            }
+            // On error, the collateral is still held by the market
+            _ => {
+                for claim in claims {
+                    let mut outcome_token = self.get_outcome_token(claim.outcome_id);
+                    outcome_token.mint(&payee, claim.amount);
+                    self.outcome_tokens
+                        .insert(&claim.outcome_id, &outcome_token);
+                }
+
+                self.update_ct_balance(self.collateral_token.balance + amount_payable);
+
+                0.to_string()
+            }
+        }
+    }
+
+    #[private]
+    pub fn on_ft_metadata_callback(&mut self) -> CollateralToken {
+        let metadata: FungibleTokenMetadata = match env::promise_result(0) {
+            PromiseResult::Successful(result) => {
+                serde_json::from_slice(&result).expect("ERR_ON_FT_METADATA")
+            }
+            _ => env::panic_str("ERR_ON_FT_METADATA_CALLBACK"),
+        };
+
+        log!(
+            "on_ft_metadata_callback: symbol: {}, decimals: {}",
+            metadata.symbol,
+            metadata.decimals
+        );
+
+        self.collateral_token.decimals = metadata.decimals;
+        self.collateral_token.symbol = Some(metadata.symbol);
+        self.collateral_token.icon = metadata.icon;
+        self.collateral_token.is_verified = true;
+
+        self.collateral_token.clone()
+    }
+}
+
+impl Market {
+    /**
+     * Mints back the outcome tokens burnt by fn internal_sell and restores the collateral balance
+     */
+    fn internal_revert_sell(
+        &mut self,
+        payee: &AccountId,
+        outcome_id: OutcomeId,
+        amount: WrappedBalance,
+        amount_payable: WrappedBalance,
+    ) {
+        let mut outcome_token = self.get_outcome_token(outcome_id);
+        outcome_token.mint(payee, amount);
+        self.outcome_tokens.insert(&outcome_id, &outcome_token);
+
+        self.update_ct_balance(self.collateral_token.balance + amount_payable);
+    }
 }
@@ -6,16 +6,37 @@ pub const GAS_CREATE_DAO_PROPOSAL_CALLBACK: Gas = Gas(8_000_000_000_000);
 pub const GAS_FT_TRANSFER: Gas = Gas(3_000_000_000_000);
 pub const GAS_FT_BALANCE_OF: Gas = Gas(3_000_000_000_000);
 pub const GAS_FT_BALANCE_OF_CALLBACK: Gas = Gas(3_000_000_000_000);
+// Includes GAS_UPDATE_MARKET_STATUS, the factory is notified before the account is deleted
+pub const GAS_FT_BALANCE_OF_FINALIZE_CALLBACK: Gas = Gas(10_000_000_000_000);
 pub const GAS_FT_TRANSFER_CALLBACK: Gas = Gas(3_000_000_000_000);
 pub const GAS_FT_TOTAL_SUPPLY: Gas = Gas(2_000_000_000_000);
 pub const GAS_FT_TOTAL_SUPPLY_CALLBACK: Gas = Gas(2_000_000_000_000);
 pub const GAS_FT_METADATA: Gas = Gas(2_000_000_000_000);
 pub const GAS_FT_METADATA_CALLBACK: Gas = Gas(2_000_000_000_000);
 pub const GAS_AGGREGATOR_READ: Gas = Gas(8_000_000_000_000);
+pub const GAS_NEAR_DEPOSIT: Gas = Gas(5_000_000_000_000);
+// Covers either the buy or the refund near_withdraw and its callback, see fn internal_refund_buy_with_near
+pub const GAS_NEAR_DEPOSIT_CALLBACK: Gas = Gas(25_000_000_000_000);
+pub const GAS_NEAR_WITHDRAW: Gas = Gas(5_000_000_000_000);
+pub const GAS_NEAR_WITHDRAW_CALLBACK: Gas = Gas(5_000_000_000_000);
+pub const GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED: Gas = Gas(5_000_000_000_000);
+pub const GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED_CALLBACK: Gas = Gas(15_000_000_000_000);
+// Covers the near_deposit and its callback, see fn buy_with_near
+pub const GAS_ALLOW_LIST_REGISTRY_IS_ALLOWED_WITH_NEAR_CALLBACK: Gas = Gas(40_000_000_000_000);
+pub const GAS_DISTRIBUTE_PAYOUT_CALLBACK: Gas = Gas(5_000_000_000_000);
+pub const GAS_UPDATE_MARKET_STATUS: Gas = Gas(5_000_000_000_000);
+pub const GAS_UPDATE_MARKET_DATA: Gas = Gas(5_000_000_000_000);
+pub const GAS_MIGRATE: Gas = Gas(20_000_000_000_000);
 
 pub const BALANCE_PROPOSAL_BOND: Balance = 100_000_000_000_000_000_000_000; // 0.1 Near
 pub const FT_TRANSFER_BOND: Balance = ONE_YOCTO;
 
+// 90 days, the max delay the DAO may postpone an event by on each fn reschedule
+pub const MAX_RESCHEDULE_DELAY: i64 = 7_776_000 * 1_000_000_000;
+
+// Each payout takes a transfer and a callback, keeps fn distribute_payouts within the 300 TGas limit
+pub const MAX_PAYOUTS_PER_DISTRIBUTION: u64 = 20;
+
 pub const FORMATTED_STRING_LOCALE: Locale = Locale::en;
 
 pub const FEED_PARSER_V2_MAINNET: &str = "feed-parser.pulsemarkets.near";
@@ -25,3 +46,11 @@ pub const FEED_PARSER_ACCOUNT_ID: &str = FEED_PARSER_V2_MAINNET;
 pub const FEED_PARSER_V2_TESTNET: &str = "feed-1.pulsemarkets.testnet";
 #[cfg(near_env = "testnet")]
 pub const FEED_PARSER_ACCOUNT_ID: &str = FEED_PARSER_V2_TESTNET;
+
+pub const WRAP_NEAR_MAINNET: &str = "wrap.near";
+#[cfg(not(near_env = "testnet"))]
+pub const WRAP_NEAR_ACCOUNT_ID: &str = WRAP_NEAR_MAINNET;
+
+pub const WRAP_NEAR_TESTNET: &str = "wrap.testnet";
+#[cfg(near_env = "testnet")]
+pub const WRAP_NEAR_ACCOUNT_ID: &str = WRAP_NEAR_TESTNET;
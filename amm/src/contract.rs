@@ -1,12 +1,14 @@
 use near_sdk::{
-    collections::LookupMap, env, ext_contract, json_types::U128, log, near_bindgen, serde_json,
-    AccountId, Promise,
+    collections::{LookupMap, LookupSet, UnorderedSet},
+    env, ext_contract,
+    json_types::U128,
+    log, near_bindgen, serde_json, AccountId, Promise,
 };
 use num_format::ToFormattedString;
-use shared::OutcomeId;
+use shared::{MarketSummaryStatus, OutcomeId, ResolutionEvidence};
 use std::default::Default;
 
-use near_contract_standards::fungible_token::core::ext_ft_core;
+use near_contract_standards::fungible_token::{core::ext_ft_core, metadata::ext_ft_metadata};
 
 use crate::consts::*;
 use crate::storage::*;
@@ -20,6 +22,40 @@ trait Callbacks {
         outcome_id: OutcomeId,
         amount_payable: WrappedBalance,
     ) -> String;
+    fn on_ft_metadata_callback(&mut self) -> CollateralToken;
+    fn on_near_deposit_callback(
+        &mut self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> WrappedBalance;
+    fn on_near_withdraw_callback(
+        &mut self,
+        amount: WrappedBalance,
+        payee: AccountId,
+        outcome_id: OutcomeId,
+        amount_payable: WrappedBalance,
+    ) -> String;
+    fn on_buy_with_near_refund_callback(&mut self, sender_id: AccountId, amount: WrappedBalance);
+    fn on_claim_all_callback(
+        &mut self,
+        payee: AccountId,
+        receiver_id: AccountId,
+        claims: Vec<Claim>,
+        amount_payable: WrappedBalance,
+    ) -> String;
+}
+
+#[ext_contract(ext_wrap_near)]
+trait WrapNear {
+    fn near_deposit(&mut self);
+    fn near_withdraw(&mut self, amount: U128) -> Promise;
+}
+
+#[ext_contract(ext_market_factory)]
+trait MarketFactory {
+    fn update_market_status(&mut self, status: MarketSummaryStatus);
+    fn update_market_data(&mut self, category: Option<String>, starts_at: i64, ends_at: i64);
 }
 
 #[ext_contract(ext_feed_parser)]
@@ -43,40 +79,69 @@ impl Market {
         collateral_token: CollateralToken,
         fees: Fees,
         price: Option<Pricing>,
+        limits: Option<Limits>,
+        allow_list: Option<AllowListArgs>,
     ) -> Self {
         if env::state_exists() {
             env::panic_str("ERR_ALREADY_INITIALIZED");
         }
 
-        if market.options.len() < 2 {
-            env::panic_str("ERR_NEW_INSUFFICIENT_MARKET_OPTIONS");
+        Self::assert_is_valid_market_data(&market, resolution.window);
+
+        match fees.claiming_window {
+            Some(claiming_window) => {
+                if resolution.window >= claiming_window {
+                    env::panic_str("ERR_NEW_RESOLUTION_WINDOW_MUST_BE_BEFORE_CLAIMING_WINDOW");
+                }
+            }
+            None => env::panic_str("ERR_NEW_CLAIMING_WINDOW_NOT_SET"),
         }
 
         // yes/no price markets have this value set upon creation
         let price_market = if let Some(p) = price { Some(p) } else { None };
 
-        let resolution_window = resolution.window;
-        let claiming_window = Some(resolution_window + 2592000 * 1_000_000_000);
+        // restricted markets only let allowed accounts buy
+        let allow_list = allow_list.map(|args| {
+            let mut accounts = UnorderedSet::new(StorageKeys::AllowList);
+            accounts.extend(args.accounts);
+
+            AllowList {
+                accounts,
+                registry_account_id: args.registry_account_id,
+            }
+        });
 
         Self {
             market: MarketData { ..market },
             collateral_token: CollateralToken {
                 balance: 0,
                 fee_balance: 0,
-                // @TODO collateral_token_decimals should be set by a cross-contract call to ft_metadata, otherwise the system can be tamed
+                // decimals, symbol and icon are verified by fn set_collateral_token_metadata
+                is_verified: false,
                 ..collateral_token
             },
             outcome_tokens: LookupMap::new(StorageKeys::OutcomeTokens),
-            resolution,
+            resolution: Resolution {
+                winning_outcome_id: None,
+                evidence: None,
+                invalidated_at: None,
+                ..resolution
+            },
             management,
             fees: Fees {
                 staking_fees: Some(LookupMap::new(StorageKeys::StakingFees)),
                 market_creator_fees: Some(LookupMap::new(StorageKeys::MarketCreatorFees)),
-                // @TODO set to less time, currently 30 days after resolution window
-                claiming_window,
                 ..fees
             },
             price: price_market,
+            limits: limits.unwrap_or_default(),
+            collateral_spent: LookupMap::new(StorageKeys::CollateralSpent),
+            allow_list,
+            postponed_at: None,
+            positions: LookupMap::new(StorageKeys::Positions),
+            payout_distribution_index: LookupMap::new(StorageKeys::PayoutDistributionIndex),
+            operators: LookupSet::new(StorageKeys::Operators),
+            factory_account_id: env::predecessor_account_id(),
         }
     }
 
@@ -106,8 +171,11 @@ impl Market {
         amount: WrappedBalance,
         payload: BuyArgs,
     ) -> WrappedBalance {
-        self.assert_is_open();
-        self.assert_is_not_resolved();
+        self.assert_is_allowed(sender_id.clone());
+
+        if let Some(error) = self.internal_get_buy_error(&sender_id, amount, payload.outcome_id) {
+            env::panic_str(error);
+        }
 
         let mut outcome_token = self.get_outcome_token(payload.outcome_id);
 
@@ -125,8 +193,13 @@ impl Market {
         );
 
         outcome_token.mint(&sender_id, amount_mintable);
+        self.collateral_spent.insert(
+            &sender_id,
+            &(self.get_collateral_spent(sender_id.clone()) + amount),
+        );
         self.update_ct_balance(self.collateral_token.balance + amount);
         self.update_ct_fee_balance(fee);
+        self.internal_update_position_on_buy(&sender_id, payload.outcome_id, amount, fee);
 
         self.outcome_tokens
             .insert(&payload.outcome_id, &outcome_token);
@@ -134,6 +207,42 @@ impl Market {
         return amount_mintable;
     }
 
+    /**
+     * Lets accounts purchase OTs with native NEAR when the collateral token is wNEAR
+     * The attached deposit is wrapped by near_deposit and then bought as if it was sent through ft_on_transfer
+     * Every check of fn buy runs before the deposit is wrapped
+     *
+     * @notice only while the market is open
+     *
+     * @param outcome_id, the id of the OT to buy
+     *
+     * @returns Promise
+     */
+    #[payable]
+    pub fn buy_with_near(&mut self, outcome_id: OutcomeId) -> Promise {
+        self.assert_is_collateral_token_wrap_near();
+
+        let sender_id = env::predecessor_account_id();
+        let amount = env::attached_deposit();
+
+        if amount == 0 {
+            env::panic_str("ERR_BUY_WITH_NEAR_ZERO_AMOUNT");
+        }
+
+        if let Some(error) = self.internal_get_buy_error(&sender_id, amount, outcome_id) {
+            env::panic_str(error);
+        }
+
+        // restricted markets may check the account against an external registry first, as in ft_on_transfer
+        if let Some(promise) =
+            self.internal_allow_list_registry_check_with_near(&sender_id, amount, outcome_id)
+        {
+            return promise;
+        }
+
+        self.internal_buy_with_near(sender_id, amount, outcome_id)
+    }
+
     /**
      * An account may sell their OTs and get their CT back
      * No lp_fee is charged on this transaction
@@ -152,20 +261,131 @@ impl Market {
      *
      * @param outcome_id, the id of the desired OT balance to sell
      * @param balance, how much of OTs balance to sell
+     * @param account_id, the holder to sell for, the predecessor must be an approved operator
      *
      * @returns amount of CT sold
      */
     #[payable]
-    pub fn sell(&mut self, outcome_id: OutcomeId, amount: WrappedBalance) -> WrappedBalance {
+    pub fn sell(
+        &mut self,
+        outcome_id: OutcomeId,
+        amount: WrappedBalance,
+        account_id: Option<AccountId>,
+    ) -> WrappedBalance {
+        let payee = self.internal_get_holder(account_id);
+
         // @TODO if there are participants only in 1 outcome, allow to claim funds after resolution, otherwise funds will be locked
         if self.is_expired_unresolved() {
-            return self.internal_sell(outcome_id, amount);
+            return self.internal_sell(outcome_id, amount, payee);
         }
 
         self.assert_is_not_under_resolution();
         self.assert_is_resolved();
 
-        return self.internal_sell(outcome_id, amount);
+        return self.internal_sell(outcome_id, amount, payee);
+    }
+
+    /**
+     * Sells the whole balance of every outcome token held by the predecessor
+     * Payouts are added up and paid with a single transfer
+     *
+     * @notice only after the market is resolved, or if it expired unresolved
+     *
+     * @param receiver_id, the account to send the proceeds to, defaults to the holder, only the holder may set another account
+     * @param account_id, the holder to claim for, the predecessor must be an approved operator
+     *
+     * @returns the total amount payable
+     */
+    pub fn claim_all(
+        &mut self,
+        receiver_id: Option<AccountId>,
+        account_id: Option<AccountId>,
+    ) -> WrappedBalance {
+        if !self.is_expired_unresolved() {
+            self.assert_is_not_under_resolution();
+            self.assert_is_resolved();
+        }
+
+        let payee = self.internal_get_holder(account_id);
+
+        // An operator may only claim to the holder
+        let receiver_id = match receiver_id {
+            Some(receiver_id) if receiver_id != payee && payee != env::predecessor_account_id() => {
+                env::panic_str("ERR_CLAIM_ALL_RECEIVER_IS_NOT_HOLDER")
+            }
+            Some(receiver_id) => receiver_id,
+            None => payee.clone(),
+        };
+
+        let mut claims: Vec<Claim> = vec![];
+        let mut amount_payable: WrappedBalance = 0;
+
+        for outcome_id in self.get_outcome_ids() {
+            let outcome_token = self.get_outcome_token(outcome_id);
+            let amount = outcome_token.get_balance(&payee);
+
+            if amount == 0 || !outcome_token.is_active() {
+                continue;
+            }
+
+            let (outcome_amount_payable, _weight) =
+                self.get_amount_payable(amount, outcome_id, payee.clone());
+
+            if outcome_amount_payable == 0 {
+                continue;
+            }
+
+            amount_payable += outcome_amount_payable;
+            claims.push(Claim {
+                outcome_id,
+                amount,
+                amount_payable: outcome_amount_payable,
+            });
+        }
+
+        if claims.is_empty() {
+            env::panic_str("ERR_CLAIM_ALL_NOTHING_TO_CLAIM");
+        }
+
+        // Burn before the transfer so that the holder can't claim twice while it is in flight
+        for claim in claims.iter() {
+            let mut outcome_token = self.get_outcome_token(claim.outcome_id);
+            outcome_token.burn(&payee, claim.amount);
+            self.outcome_tokens
+                .insert(&claim.outcome_id, &outcome_token);
+        }
+
+        self.update_ct_balance(self.collateral_token.balance - amount_payable);
+
+        log!(
+            "CLAIM_ALL account_id: {}, receiver_id: {}, outcomes: {}, amount_payable: {}",
+            payee,
+            receiver_id,
+            claims.len(),
+            amount_payable.to_formatted_string(&FORMATTED_STRING_LOCALE),
+        );
+
+        let callback_promise = ext_self::ext(env::current_account_id())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_FT_TRANSFER_CALLBACK)
+            .on_claim_all_callback(payee, receiver_id.clone(), claims, amount_payable);
+
+        // wNEAR markets are paid out in native NEAR
+        if self.is_collateral_token_wrap_near() {
+            ext_wrap_near::ext(self.collateral_token.id.clone())
+                .with_attached_deposit(FT_TRANSFER_BOND)
+                .with_static_gas(GAS_NEAR_WITHDRAW)
+                .near_withdraw(U128::from(amount_payable))
+                .then(callback_promise);
+        } else {
+            ext_ft_core::ext(self.collateral_token.id.clone())
+                .with_attached_deposit(FT_TRANSFER_BOND)
+                .with_static_gas(GAS_FT_TRANSFER)
+                .ft_transfer(receiver_id, U128::from(amount_payable), None)
+                .then(callback_promise);
+        }
+
+        amount_payable
     }
 
     /**
@@ -178,19 +398,55 @@ impl Market {
      * @notice only after the market start_date and end_date period is over
      * @notice only by a Sputnik2 DAO Function Call Proposal!!
      *
+     * @param evidence, the oracle round result the winning outcome was picked with, kept only if sent by the feed parser
+     *
      * @returns
      */
     #[payable]
-    pub fn resolve(&mut self, outcome_id: OutcomeId, ix: Ix) {
+    pub fn resolve(&mut self, outcome_id: OutcomeId, ix: Ix, evidence: Option<ResolutionEvidence>) {
         self.assert_only_owner(ix);
         self.assert_is_not_resolved();
         self.assert_is_valid_outcome(outcome_id);
+        self.assert_is_not_postponed();
 
         self.assert_is_resolution_window_open();
 
         self.burn_the_losers(outcome_id);
 
         self.resolution.resolved_at = Some(self.get_block_timestamp());
+        self.resolution.winning_outcome_id = Some(outcome_id);
+        // Only the feed parser reads the evidence from the aggregator, any other caller would be asserting it
+        if env::predecessor_account_id().as_str() == FEED_PARSER_ACCOUNT_ID {
+            self.resolution.evidence = evidence;
+        }
+
+        self.internal_notify_factory(MarketSummaryStatus::Resolved);
+    }
+
+    /**
+     * Invalidates the market, holders sell their outcome tokens back for the collateral spent, as if it expired unresolved
+     *
+     * @notice by the DAO if the market can't be resolved, by anyone once the resolution window expired
+     */
+    pub fn invalidate(&mut self) {
+        self.assert_is_not_resolved();
+
+        if self.resolution.invalidated_at.is_some() {
+            env::panic_str("ERR_MARKET_IS_INVALIDATED");
+        }
+
+        if !self.is_expired_unresolved() {
+            self.assert_only_dao();
+        }
+
+        self.resolution.invalidated_at = Some(self.get_block_timestamp());
+
+        // Only the DAO invalidating the market slashes the creator bond, an oracle or DAO outage does not
+        if env::predecessor_account_id() == self.management.dao_account_id {
+            self.internal_notify_factory(MarketSummaryStatus::Invalid);
+        } else {
+            self.internal_notify_factory(MarketSummaryStatus::Expired);
+        }
     }
 
     pub fn create_outcome_tokens(&mut self) -> usize {
@@ -206,6 +462,242 @@ impl Market {
         }
     }
 
+    /**
+     * Lets the market creator fix the market metadata and reschedule its dates
+     * Outcome tokens are recreated if the number of options changed
+     *
+     * @notice only by the market creator, before the first buy
+     *
+     * @param market, the new market metadata, close_at must be in the future and ends_at before the resolution window
+     */
+    pub fn update_market(&mut self, market: MarketData) {
+        self.assert_only_market_creator();
+        self.assert_is_not_traded();
+
+        Self::assert_is_valid_market_data(&market, self.resolution.window);
+
+        // A close_at in the past would move an open market straight to resolution
+        if market.close_at <= self.get_block_timestamp() {
+            env::panic_str("ERR_UPDATE_MARKET_CLOSE_AT_MUST_BE_IN_THE_FUTURE");
+        }
+
+        let previous_options_len = self.market.options.len();
+        let options_len = market.options.len();
+
+        if self.outcome_tokens.contains_key(&0) {
+            for outcome_id in options_len..previous_options_len {
+                let mut outcome_token = self.get_outcome_token(outcome_id as OutcomeId);
+                outcome_token.balances.clear();
+                self.outcome_tokens.remove(&(outcome_id as OutcomeId));
+            }
+
+            for outcome_id in previous_options_len..options_len {
+                self.create_outcome_token(outcome_id as OutcomeId);
+            }
+        }
+
+        log!(
+            "update_market: {}, options: {}, starts_at: {}, close_at: {}, ends_at: {}",
+            market.description,
+            options_len,
+            market.starts_at,
+            market.close_at,
+            market.ends_at
+        );
+
+        self.market = market;
+
+        self.internal_notify_factory_market_data();
+    }
+
+    /**
+     * Deletes the market account, its NEAR balance — the deposit attached by the factory — is sent to the market creator
+     * The factory cancels the market if its creation could not be completed
+     *
+     * @notice only by the market creator or the factory, before the first buy
+     *
+     * @returns Promise
+     */
+    pub fn cancel(&mut self) -> Promise {
+        self.assert_only_market_creator_or_factory();
+        self.assert_is_not_traded();
+
+        log!(
+            "cancel: deleting {}, beneficiary: {}",
+            env::current_account_id(),
+            self.management.market_creator_account_id
+        );
+
+        // A market cancelled by the factory was never listed, see MarketFactory::on_create_outcome_tokens_ft_storage_deposit_callback
+        if env::predecessor_account_id() != self.factory_account_id {
+            self.internal_notify_factory(MarketSummaryStatus::Cancelled);
+        }
+
+        Promise::new(env::current_account_id())
+            .delete_account(self.management.market_creator_account_id.clone())
+    }
+
+    /**
+     * Deploys a new AMM version to this market account and migrates its state
+     * The code is the raw input of this call, sent by MarketFactory::upgrade_markets
+     *
+     * @notice only by the factory
+     *
+     * @returns Promise
+     */
+    pub fn upgrade(&mut self) -> Promise {
+        self.assert_only_factory();
+
+        let code = match env::input() {
+            Some(code) if !code.is_empty() => code,
+            _ => env::panic_str("ERR_UPGRADE_CODE_NOT_SET"),
+        };
+
+        log!(
+            "upgrade: deploying {} bytes to {}",
+            code.len(),
+            env::current_account_id()
+        );
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call("migrate".to_string(), vec![], 0, GAS_MIGRATE)
+    }
+
+    /**
+     * Reads the state written by the previous AMM version as an OldMarket and converts it
+     * Versions that change the Market layout set the new fields here
+     *
+     * @returns the migrated Market
+     */
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_market: OldMarket = match env::state_read() {
+            Some(old_market) => old_market,
+            None => env::panic_str("ERR_MIGRATE_STATE_NOT_FOUND"),
+        };
+
+        Self {
+            market: old_market.market,
+            collateral_token: old_market.collateral_token,
+            fees: old_market.fees,
+            resolution: old_market.resolution,
+            management: old_market.management,
+            outcome_tokens: old_market.outcome_tokens,
+            price: old_market.price,
+            limits: old_market.limits,
+            collateral_spent: old_market.collateral_spent,
+            allow_list: old_market.allow_list,
+            postponed_at: old_market.postponed_at,
+            positions: old_market.positions,
+            payout_distribution_index: old_market.payout_distribution_index,
+            operators: old_market.operators,
+            factory_account_id: old_market.factory_account_id,
+        }
+    }
+
+    /**
+     * Postpones the event: close_at, ends_at, resolution.window and fees.claiming_window are moved by the same delay
+     * Trading is paused until fn resume
+     *
+     * @notice only by the DAO, before the market is resolved or its resolution window expires
+     *
+     * @param ends_at, the new date the event ends, at most MAX_RESCHEDULE_DELAY after the current one
+     */
+    pub fn reschedule(&mut self, ends_at: Timestamp) {
+        self.assert_only_dao();
+        self.assert_is_not_resolved();
+        self.assert_is_resolution_window_open();
+
+        if ends_at <= self.market.ends_at {
+            env::panic_str("ERR_RESCHEDULE_ENDS_AT_MUST_BE_AFTER_CURRENT_ENDS_AT");
+        }
+
+        let delay = ends_at - self.market.ends_at;
+
+        if delay > MAX_RESCHEDULE_DELAY {
+            env::panic_str("ERR_RESCHEDULE_DELAY_EXCEEDS_MAX");
+        }
+
+        self.market.close_at += delay;
+        self.market.ends_at = ends_at;
+        self.resolution.window += delay;
+        self.fees.claiming_window = Some(self.claiming_window() + delay);
+        self.postponed_at = Some(self.get_block_timestamp());
+
+        self.internal_notify_factory_market_data();
+
+        log!(
+            "EVENT_JSON:{}",
+            serde_json::json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": "reschedule",
+                "data": [{
+                    "close_at": self.market.close_at.to_string(),
+                    "ends_at": self.market.ends_at.to_string(),
+                    "resolution_window": self.resolution.window.to_string(),
+                    "claiming_window": self.claiming_window().to_string(),
+                }]
+            })
+        );
+    }
+
+    /**
+     * Resumes trading of a postponed market
+     *
+     * @notice only by the DAO
+     */
+    pub fn resume(&mut self) {
+        self.assert_only_dao();
+
+        if !self.is_postponed() {
+            env::panic_str("ERR_MARKET_IS_NOT_POSTPONED");
+        }
+
+        self.postponed_at = None;
+
+        log!(
+            "EVENT_JSON:{}",
+            serde_json::json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": "resume",
+                "data": [{ "resumed_at": self.get_block_timestamp().to_string() }]
+            })
+        );
+    }
+
+    /**
+     * Reads the NEP148 ft_metadata of the collateral token and stores its decimals, symbol and icon
+     * Called by the MarketFactory alongside create_outcome_tokens
+     *
+     * @notice only by the factory, buys are refused until the collateral token is verified
+     *
+     * @returns Promise
+     */
+    pub fn set_collateral_token_metadata(&mut self) -> Promise {
+        // Another caller could verify the token first and fail the factory call, cancelling the market
+        self.assert_only_factory();
+
+        if self.collateral_token.is_verified {
+            env::panic_str("ERR_COLLATERAL_TOKEN_ALREADY_VERIFIED");
+        }
+
+        let ft_metadata_promise = ext_ft_metadata::ext(self.collateral_token.id.clone())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_FT_METADATA)
+            .ft_metadata();
+
+        let ft_metadata_callback_promise = ext_self::ext(env::current_account_id())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_FT_METADATA_CALLBACK)
+            .on_ft_metadata_callback();
+
+        ft_metadata_promise.then(ft_metadata_callback_promise)
+    }
+
     /**
      * attempt to call the feed-parser contract that will call "self.resolve"
      */
@@ -247,6 +739,181 @@ impl Market {
 }
 
 impl Market {
+    /**
+     * The checks of fn buy but the allow list, without panicking
+     * Lets fn buy_with_near refund the NEAR deposit instead of losing it once wrapped
+     *
+     * @returns the error fn buy would panic with, None if the account can buy
+     */
+    pub fn internal_get_buy_error(
+        &self,
+        sender_id: &AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> Option<&'static str> {
+        if !self.collateral_token.is_verified {
+            return Some("ERR_COLLATERAL_TOKEN_NOT_VERIFIED");
+        }
+
+        if self.is_postponed() {
+            return Some("ERR_MARKET_IS_POSTPONED");
+        }
+
+        let status = self.get_status();
+
+        if !matches!(status, MarketStatus::Presale | MarketStatus::Open) {
+            return Some("ERR_MARKET_IS_CLOSED");
+        }
+
+        if let Some(min_bet_amount) = self.limits.min_bet_amount {
+            if amount < min_bet_amount {
+                return Some("ERR_BUY_AMOUNT_LOWER_THAN_MIN_BET_AMOUNT");
+            }
+        }
+
+        if let Some(max_collateral) = self.limits.max_collateral_per_account {
+            if self.get_collateral_spent(sender_id.clone()) + amount > max_collateral {
+                return Some("ERR_BUY_EXCEEDS_MAX_COLLATERAL_PER_ACCOUNT");
+            }
+        }
+
+        let outcome_token = match self.outcome_tokens.get(&outcome_id) {
+            Some(outcome_token) => outcome_token,
+            None => return Some("ERR_INVALID_OUTCOME_ID"),
+        };
+
+        if let Some(max_supply) = self.limits.max_outcome_token_supply {
+            let (amount_mintable, _fee) = self.get_amount_mintable(amount);
+
+            if outcome_token.total_supply() + amount_mintable > max_supply {
+                return Some("ERR_BUY_EXCEEDS_MAX_OUTCOME_TOKEN_SUPPLY");
+            }
+        }
+
+        None
+    }
+
+    /**
+     * Wraps the NEAR deposit and buys with it, see fn on_near_deposit_callback
+     *
+     * @returns Promise
+     */
+    pub fn internal_buy_with_near(
+        &self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+        outcome_id: OutcomeId,
+    ) -> Promise {
+        let near_deposit_promise = ext_wrap_near::ext(self.collateral_token.id.clone())
+            .with_attached_deposit(amount)
+            .with_static_gas(GAS_NEAR_DEPOSIT)
+            .near_deposit();
+
+        let near_deposit_callback_promise = ext_self::ext(env::current_account_id())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_NEAR_DEPOSIT_CALLBACK)
+            .on_near_deposit_callback(sender_id, amount, outcome_id);
+
+        near_deposit_promise.then(near_deposit_callback_promise)
+    }
+
+    /**
+     * Unwraps a deposit fn buy_with_near could not buy with and sends it back
+     *
+     * @returns Promise
+     */
+    pub fn internal_refund_buy_with_near(
+        &self,
+        sender_id: AccountId,
+        amount: WrappedBalance,
+    ) -> Promise {
+        let near_withdraw_promise = ext_wrap_near::ext(self.collateral_token.id.clone())
+            .with_attached_deposit(FT_TRANSFER_BOND)
+            .with_static_gas(GAS_NEAR_WITHDRAW)
+            .near_withdraw(U128::from(amount));
+
+        let near_withdraw_callback_promise = ext_self::ext(env::current_account_id())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_NEAR_WITHDRAW_CALLBACK)
+            .on_buy_with_near_refund_callback(sender_id, amount);
+
+        near_withdraw_promise.then(near_withdraw_callback_promise)
+    }
+
+    fn assert_is_valid_market_data(market: &MarketData, resolution_window: Timestamp) {
+        if market.options.len() < 2 {
+            env::panic_str("ERR_NEW_INSUFFICIENT_MARKET_OPTIONS");
+        }
+
+        if market.starts_at >= market.close_at {
+            env::panic_str("ERR_STARTS_AT_MUST_BE_BEFORE_CLOSE_AT");
+        }
+
+        if market.close_at > market.ends_at {
+            env::panic_str("ERR_CLOSE_AT_MUST_NOT_BE_AFTER_ENDS_AT");
+        }
+
+        if market.ends_at >= resolution_window {
+            env::panic_str("ERR_ENDS_AT_MUST_BE_BEFORE_RESOLUTION_WINDOW");
+        }
+    }
+
+    fn internal_update_position_on_buy(
+        &mut self,
+        account_id: &AccountId,
+        outcome_id: OutcomeId,
+        amount: WrappedBalance,
+        fee: WrappedBalance,
+    ) {
+        let key = (account_id.clone(), outcome_id);
+        let mut position = self.positions.get(&key).unwrap_or_default();
+
+        position.collateral_spent += amount;
+        position.fees_paid += fee;
+
+        self.positions.insert(&key, &position);
+    }
+
+    pub fn internal_update_position_on_sell(
+        &mut self,
+        account_id: &AccountId,
+        outcome_id: OutcomeId,
+        amount_payable: WrappedBalance,
+    ) {
+        let key = (account_id.clone(), outcome_id);
+        let mut position = self.positions.get(&key).unwrap_or_default();
+
+        position.proceeds += amount_payable;
+
+        self.positions.insert(&key, &position);
+    }
+
+    /**
+     * Reports a status change to the MarketFactory that deployed this market, see MarketFactory::update_market_status
+     *
+     * @param status, the new status of this market
+     */
+    pub fn internal_notify_factory(&self, status: MarketSummaryStatus) {
+        ext_market_factory::ext(self.factory_account_id.clone())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_UPDATE_MARKET_STATUS)
+            .update_market_status(status);
+    }
+
+    /**
+     * Reports the market dates and category to the MarketFactory, so that its market summary stays up to date
+     */
+    pub fn internal_notify_factory_market_data(&self) {
+        ext_market_factory::ext(self.factory_account_id.clone())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_UPDATE_MARKET_DATA)
+            .update_market_data(
+                self.market.category.clone(),
+                self.market.starts_at,
+                self.market.ends_at,
+            );
+    }
+
     fn burn_the_losers(&mut self, outcome_id: OutcomeId) {
         for id in 0..self.market.options.len() {
             let mut outcome_token = self.get_outcome_token(id as OutcomeId);
@@ -263,21 +930,23 @@ impl Market {
         self.outcome_tokens.insert(&outcome_id, &outcome_token);
     }
 
-    fn internal_sell(&mut self, outcome_id: OutcomeId, amount: WrappedBalance) -> WrappedBalance {
-        if amount > self.balance_of(outcome_id, env::signer_account_id()) {
+    fn internal_sell(
+        &mut self,
+        outcome_id: OutcomeId,
+        amount: WrappedBalance,
+        payee: AccountId,
+    ) -> WrappedBalance {
+        if amount > self.balance_of(outcome_id, payee.clone()) {
             env::panic_str("ERR_SELL_AMOUNT_GREATER_THAN_BALANCE");
         }
 
-        let (amount_payable, weight) =
-            self.get_amount_payable(amount, outcome_id, env::signer_account_id());
+        let (amount_payable, weight) = self.get_amount_payable(amount, outcome_id, payee.clone());
 
         if amount_payable <= 0 {
             env::panic_str("ERR_CANT_SELL_A_LOSING_OUTCOME");
         }
 
-        let outcome_token = self.get_outcome_token(outcome_id);
-
-        let payee = env::signer_account_id();
+        let mut outcome_token = self.get_outcome_token(outcome_id);
 
         log!(
             "SELL amount: {}, outcome_id: {}, account_id: {}, ot_balance: {}, supply: {}, is_resolved: {}, ct_balance: {},  weight: {}, amount_payable: {}",
@@ -292,6 +961,28 @@ impl Market {
             amount_payable.to_formatted_string(&FORMATTED_STRING_LOCALE),
         );
 
+        // Burnt before the transfer so the tokens can't be sold or claimed again meanwhile, restored if it fails
+        outcome_token.burn(&payee, amount);
+        self.outcome_tokens.insert(&outcome_id, &outcome_token);
+        self.update_ct_balance(self.collateral_token.balance - amount_payable);
+
+        // wNEAR markets are paid out in native NEAR
+        if self.is_collateral_token_wrap_near() {
+            let near_withdraw_promise = ext_wrap_near::ext(self.collateral_token.id.clone())
+                .with_attached_deposit(FT_TRANSFER_BOND)
+                .with_static_gas(GAS_NEAR_WITHDRAW)
+                .near_withdraw(U128::from(amount_payable));
+
+            let near_withdraw_callback_promise = ext_self::ext(env::current_account_id())
+                .with_attached_deposit(0)
+                .with_static_gas(GAS_NEAR_WITHDRAW_CALLBACK)
+                .on_near_withdraw_callback(amount, payee, outcome_id, amount_payable);
+
+            near_withdraw_promise.then(near_withdraw_callback_promise);
+
+            return amount_payable;
+        }
+
         let ft_transfer_promise = ext_ft_core::ext(self.collateral_token.id.clone())
             .with_attached_deposit(FT_TRANSFER_BOND)
             .with_static_gas(GAS_FT_TRANSFER)
@@ -4,6 +4,8 @@ use near_sdk::{
     env, json_types::U128, log, near_bindgen, serde_json, AccountId, Promise, PromiseResult,
 };
 
+use shared::MarketSummaryStatus;
+
 use crate::consts::*;
 use crate::storage::*;
 
@@ -12,6 +14,7 @@ trait Callbacks {
     fn on_claim_market_creator_fees_resolved_callback(&mut self, payee: AccountId) -> String;
     fn on_ft_balance_of_market_callback(&mut self) -> Promise;
     fn on_ft_transfer_to_dao_callback(&mut self);
+    fn on_ft_balance_of_finalize_callback(&mut self) -> Promise;
 }
 
 #[near_bindgen]
@@ -20,6 +23,7 @@ impl Market {
      * Lets fee payees claim their balance
      *
      * @notice only after market is resolved
+     * @notice by the market creator or an operator approved by the market creator
      *
      * @returns WrappedBalance of fee proportion paid
      */
@@ -27,9 +31,11 @@ impl Market {
         self.assert_is_resolved();
         self.assert_is_claiming_window_open();
 
-        let payee = env::signer_account_id();
+        let payee = self.management.market_creator_account_id.clone();
 
-        if payee != self.management.market_creator_account_id {
+        if env::predecessor_account_id() != payee
+            && !self.is_approved_operator(payee.clone(), env::predecessor_account_id())
+        {
             env::panic_str("ERR_CLAIM_MARKET_CREATOR_FEES_RESOLVED_ACCOUNT_ID_MISTMATCH");
         }
 
@@ -85,6 +91,31 @@ impl Market {
         ft_balance_of_promise.then(ft_balance_of_callback_promise)
     }
 
+    /**
+     * Deletes the market account and its storage, the remaining NEAR balance is sent to the DAO
+     *
+     * @notice only after the fees claiming window expired and claim_fees_unclaimed swept the collateral
+     *
+     * @returns Promise
+     */
+    pub fn finalize(&mut self) -> Promise {
+        if !self.is_claiming_window_expired() {
+            env::panic_str("ERR_CANNOT_FINALIZE_MARKET_BEFORE_CLAIMING_WINDOW_EXPIRATION");
+        }
+
+        let ft_balance_of_promise = ext_ft_core::ext(self.collateral_token.id.clone())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_FT_BALANCE_OF)
+            .ft_balance_of(env::current_account_id());
+
+        let ft_balance_of_callback_promise = ext_self::ext(env::current_account_id())
+            .with_attached_deposit(0)
+            .with_static_gas(GAS_FT_BALANCE_OF_FINALIZE_CALLBACK)
+            .on_ft_balance_of_finalize_callback();
+
+        ft_balance_of_promise.then(ft_balance_of_callback_promise)
+    }
+
     #[private]
     pub fn on_claim_market_creator_fees_resolved_callback(&mut self, payee: AccountId) -> String {
         let ft_transfer_result = match env::promise_result(0) {
@@ -150,6 +181,35 @@ impl Market {
         };
     }
 
+    /**
+     * Deletes the market account once its collateral token balance is 0
+     *
+     * @returns Promise
+     */
+    #[private]
+    pub fn on_ft_balance_of_finalize_callback(&mut self) -> Promise {
+        let ft_balance_of_result: U128 = match env::promise_result(0) {
+            PromiseResult::Successful(result) => {
+                serde_json::from_slice(&result).expect("ERR_ON_FT_BALANCE_OF")
+            }
+            _ => env::panic_str("ERR_ON_FT_BALANCE_OF_FINALIZE_CALLBACK"),
+        };
+
+        if ft_balance_of_result != U128(0) {
+            env::panic_str("ERR_ON_FT_BALANCE_OF_FINALIZE_CALLBACK_BALANCE_IS_NOT_0");
+        }
+
+        log!(
+            "on_ft_balance_of_finalize_callback: deleting {}, beneficiary: {}",
+            env::current_account_id(),
+            self.dao_account_id()
+        );
+
+        self.internal_notify_factory(MarketSummaryStatus::Finalized);
+
+        Promise::new(env::current_account_id()).delete_account(self.dao_account_id())
+    }
+
     pub fn get_claimed_staking_fees(&self, account_id: AccountId) -> String {
         if let Some(staking_fees) = &self.fees.staking_fees {
             match staking_fees.get(&account_id) {
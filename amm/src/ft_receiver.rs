@@ -33,7 +33,16 @@ impl FungibleTokenReceiver for Market {
         let payload: Payload = serde_json::from_str(&msg).expect("ERR_INVALID_PAYLOAD");
 
         match payload {
-            Payload::BuyArgs(payload) => self.buy(sender_id, amount, payload),
+            Payload::BuyArgs(payload) => {
+                // restricted markets may check the account against an external registry first
+                if let Some(promise) =
+                    self.internal_allow_list_registry_check(&sender_id, amount, payload.outcome_id)
+                {
+                    return PromiseOrValue::Promise(promise);
+                }
+
+                self.buy(sender_id, amount, payload)
+            }
         };
 
         // All the collateral was used, so we should issue no refund on ft_resolve_transfer
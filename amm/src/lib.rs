@@ -22,6 +22,15 @@ pub use outcome_token::*;
 pub mod fees;
 pub use fees::*;
 
+pub mod allow_list;
+pub use allow_list::*;
+
+pub mod operators;
+pub use operators::*;
+
+pub mod payouts;
+pub use payouts::*;
+
 pub mod ft_receiver;
 pub use ft_receiver::*;
 
@@ -1,4 +1,4 @@
-use near_sdk::{env, near_bindgen};
+use near_sdk::{env, near_bindgen, AccountId};
 use shared::OutcomeId;
 
 use crate::storage::*;
@@ -6,41 +6,134 @@ use crate::storage::*;
 #[near_bindgen]
 impl Market {
     pub fn assert_is_not_resolved(&self) {
-        if self.is_resolved() {
+        if matches!(
+            self.get_status(),
+            MarketStatus::Resolved | MarketStatus::Finalized
+        ) {
             env::panic_str("ERR_MARKET_RESOLVED");
         }
     }
 
     pub fn assert_is_resolved(&self) {
-        if !self.is_resolved() {
+        if !matches!(
+            self.get_status(),
+            MarketStatus::Resolved | MarketStatus::Finalized
+        ) {
             env::panic_str("ERR_MARKET_NOT_RESOLVED");
         }
     }
 
     pub fn assert_is_open(&self) {
-        if !self.is_open() {
+        self.assert_is_not_postponed();
+
+        if !matches!(
+            self.get_status(),
+            MarketStatus::Presale | MarketStatus::Open
+        ) {
             env::panic_str("ERR_MARKET_IS_CLOSED");
         }
     }
 
     pub fn assert_is_resolution_window_open(&self) {
-        if self.is_resolution_window_expired() {
+        if self.get_status() == MarketStatus::ExpiredUnresolved {
             env::panic_str("ERR_RESOLUTION_WINDOW_EXPIRED");
         }
     }
 
     pub fn assert_is_claiming_window_open(&self) {
-        if self.is_claiming_window_expired() {
+        if self.get_status() == MarketStatus::Finalized {
             env::panic_str("ERR_CLAIMING_WINDOW_EXPIRED");
         }
     }
 
     pub fn assert_is_not_under_resolution(&self) {
-        if self.is_over() && !self.is_resolved() && !self.is_resolution_window_expired() {
+        if self.get_status() == MarketStatus::UnderResolution {
             env::panic_str("ERR_MARKET_IS_UNDER_RESOLUTION");
         }
     }
 
+    pub fn assert_is_collateral_token_verified(&self) {
+        if !self.collateral_token.is_verified {
+            env::panic_str("ERR_COLLATERAL_TOKEN_NOT_VERIFIED");
+        }
+    }
+
+    pub fn assert_is_collateral_token_wrap_near(&self) {
+        if !self.is_collateral_token_wrap_near() {
+            env::panic_str("ERR_COLLATERAL_TOKEN_IS_NOT_WRAP_NEAR");
+        }
+    }
+
+    pub fn assert_is_allowed(&self, account_id: AccountId) {
+        if !self.is_allowed(account_id) {
+            env::panic_str("ERR_ACCOUNT_IS_NOT_ALLOWED");
+        }
+    }
+
+    pub fn assert_is_restricted(&self) {
+        if !self.is_restricted() {
+            env::panic_str("ERR_MARKET_IS_NOT_RESTRICTED");
+        }
+    }
+
+    pub fn assert_only_management(&self) {
+        let predecessor_account_id = env::predecessor_account_id();
+
+        if predecessor_account_id != self.management.market_creator_account_id
+            && predecessor_account_id != self.management.dao_account_id
+        {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_MANAGEMENT");
+        }
+    }
+
+    pub fn assert_is_not_postponed(&self) {
+        if self.is_postponed() {
+            env::panic_str("ERR_MARKET_IS_POSTPONED");
+        }
+    }
+
+    pub fn assert_only_dao(&self) {
+        if env::predecessor_account_id() != self.management.dao_account_id {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_DAO");
+        }
+    }
+
+    pub fn assert_only_market_creator(&self) {
+        if env::predecessor_account_id() != self.management.market_creator_account_id {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_MARKET_CREATOR");
+        }
+    }
+
+    pub fn assert_only_factory(&self) {
+        if env::predecessor_account_id() != self.factory_account_id {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_FACTORY");
+        }
+    }
+
+    pub fn assert_only_market_creator_or_factory(&self) {
+        let predecessor_account_id = env::predecessor_account_id();
+
+        if predecessor_account_id != self.management.market_creator_account_id
+            && predecessor_account_id != self.factory_account_id
+        {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_MARKET_CREATOR_OR_FACTORY");
+        }
+    }
+
+    pub fn assert_is_not_traded(&self) {
+        if self.collateral_token.balance > 0 {
+            env::panic_str("ERR_MARKET_HAS_TRADES");
+        }
+
+        for outcome_id in self.get_outcome_ids() {
+            if let Some(outcome_token) = self.outcome_tokens.get(&outcome_id) {
+                if outcome_token.total_supply() > 0 {
+                    env::panic_str("ERR_MARKET_HAS_TRADES");
+                }
+            }
+        }
+    }
+
     pub fn assert_only_owner(&self, ix: Ix) {
         if self.resolution.ix.address != ix.address {
             env::panic_str("ERR_SIGNER_IS_NOT_OWNER");
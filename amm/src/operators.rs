@@ -0,0 +1,104 @@
+use near_sdk::{assert_one_yocto, env, log, near_bindgen, AccountId, Balance, Promise};
+
+use crate::storage::*;
+
+#[near_bindgen]
+impl Market {
+    /**
+     * Lets another account or contract sell and claim on behalf of the predecessor
+     * Proceeds are always paid to the holder
+     *
+     * @notice the deposit must cover the approval storage, the excess is refunded
+     *
+     * @param operator_id, the account to approve
+     */
+    #[payable]
+    pub fn approve_operator(&mut self, operator_id: AccountId) {
+        let account_id = env::predecessor_account_id();
+
+        // At least one yocto, so that a function call access key can't approve an operator
+        if env::attached_deposit() == 0 {
+            env::panic_str("ERR_APPROVE_OPERATOR_REQUIRES_DEPOSIT");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.operators
+            .insert(&(account_id.clone(), operator_id.clone()));
+
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+
+        if env::attached_deposit() < storage_cost {
+            env::panic_str("ERR_APPROVE_OPERATOR_INSUFFICIENT_DEPOSIT");
+        }
+
+        if env::attached_deposit() > storage_cost {
+            Promise::new(account_id.clone()).transfer(env::attached_deposit() - storage_cost);
+        }
+
+        log!(
+            "APPROVE_OPERATOR account_id: {}, operator_id: {}",
+            account_id,
+            operator_id
+        );
+    }
+
+    /**
+     * @notice requires 1 yocto, the approval storage deposit is refunded
+     *
+     * @param operator_id, the account to revoke the approval from
+     */
+    #[payable]
+    pub fn revoke_operator(&mut self, operator_id: AccountId) {
+        assert_one_yocto();
+
+        let account_id = env::predecessor_account_id();
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.operators
+            .remove(&(account_id.clone(), operator_id.clone()));
+
+        let storage_refund =
+            Balance::from(initial_storage_usage - env::storage_usage()) * env::storage_byte_cost();
+
+        if storage_refund > 0 {
+            Promise::new(account_id.clone()).transfer(storage_refund);
+        }
+
+        log!(
+            "REVOKE_OPERATOR account_id: {}, operator_id: {}",
+            account_id,
+            operator_id
+        );
+    }
+
+    pub fn is_approved_operator(&self, account_id: AccountId, operator_id: AccountId) -> bool {
+        self.operators.contains(&(account_id, operator_id))
+    }
+}
+
+impl Market {
+    /**
+     * @notice the predecessor must be the holder or an operator approved by the holder
+     *
+     * @param account_id, the holder to act for, defaults to the predecessor
+     *
+     * @returns the holder account_id
+     */
+    pub fn internal_get_holder(&self, account_id: Option<AccountId>) -> AccountId {
+        let predecessor_account_id = env::predecessor_account_id();
+
+        match account_id {
+            Some(account_id) if account_id != predecessor_account_id => {
+                if !self.is_approved_operator(account_id.clone(), predecessor_account_id) {
+                    env::panic_str("ERR_PREDECESSOR_IS_NOT_APPROVED_OPERATOR");
+                }
+
+                account_id
+            }
+            _ => predecessor_account_id,
+        }
+    }
+}
@@ -68,7 +68,14 @@ impl OutcomeToken {
         assert!(balance >= amount, "ERR_BURN_INSUFFICIENT_BALANCE");
 
         let new_balance = balance - amount;
-        self.balances.insert(account_id, &new_balance);
+
+        // prune empty balances so that holders iteration stays meaningful
+        if new_balance == 0 {
+            self.balances.remove(account_id);
+        } else {
+            self.balances.insert(account_id, &new_balance);
+        }
+
         self.total_supply -= amount;
         self.accounts_length -= if new_balance == 0 && self.accounts_length != 0 {
             1
@@ -103,6 +110,28 @@ impl OutcomeToken {
         self.balances.get(account_id).unwrap_or(0)
     }
 
+    /**
+     * @notice returns a page of the accounts holding this token
+     * @param from_index, the index of the first holder
+     * @param limit, the max number of holders to return
+     * @returns pairs of account_id and balance
+     */
+    pub fn get_holders(&self, from_index: u64, limit: u64) -> Vec<(AccountId, WrappedBalance)> {
+        let keys = self.balances.keys_as_vector();
+        let values = self.balances.values_as_vector();
+
+        (from_index..std::cmp::min(from_index.saturating_add(limit), keys.len()))
+            .filter_map(|index| Some((keys.get(index)?, values.get(index)?)))
+            .collect()
+    }
+
+    /**
+     * @returns the number of accounts holding this token
+     */
+    pub fn get_holders_count(&self) -> u64 {
+        self.balances.len()
+    }
+
     /**
      *
      */
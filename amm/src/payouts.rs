@@ -0,0 +1,163 @@
+use near_contract_standards::fungible_token::core::ext_ft_core;
+use near_sdk::{
+    env, ext_contract, json_types::U128, log, near_bindgen, AccountId, Promise, PromiseResult,
+};
+use num_format::ToFormattedString;
+use shared::OutcomeId;
+
+use crate::consts::*;
+use crate::contract::ext_wrap_near;
+use crate::storage::*;
+
+#[ext_contract(ext_self)]
+trait Callbacks {
+    fn on_distribute_payout_callback(
+        &mut self,
+        payee: AccountId,
+        outcome_id: OutcomeId,
+        amount: WrappedBalance,
+        amount_payable: WrappedBalance,
+    ) -> WrappedBalance;
+}
+
+#[near_bindgen]
+impl Market {
+    /**
+     * Pays out a page of holders of an outcome token on their behalf
+     * Holders are paid as if they called fn sell with their whole balance
+     *
+     * Paid holders are burnt right away, so the remaining ones are always found from the stored cursor
+     * Holders that can't be paid are skipped and the cursor moves past them, pass from_index 0 to check them again
+     *
+     * @notice permissionless, only after the market is resolved or if it expired unresolved
+     *
+     * @param outcome_id, the outcome token whose holders are paid
+     * @param from_index, the index of the first holder, defaults to the stored cursor
+     * @param limit, the max number of holders to pay, capped to MAX_PAYOUTS_PER_DISTRIBUTION
+     *
+     * @returns the cursor to resume the distribution from
+     */
+    pub fn distribute_payouts(
+        &mut self,
+        outcome_id: OutcomeId,
+        from_index: Option<u64>,
+        limit: u64,
+    ) -> u64 {
+        if !self.is_expired_unresolved() {
+            self.assert_is_not_under_resolution();
+            self.assert_is_resolved();
+        }
+
+        self.assert_is_valid_outcome(outcome_id);
+
+        let outcome_token = self.get_outcome_token(outcome_id);
+
+        if !outcome_token.is_active() {
+            env::panic_str("ERR_CANT_SELL_A_LOSING_OUTCOME");
+        }
+
+        let stored_index = self.get_payout_distribution_index(outcome_id);
+        let from_index = from_index.unwrap_or(stored_index);
+        let mut index = from_index;
+
+        // A paid holder is replaced by the last one, so the same index is read again until it holds a skipped holder
+        for _ in 0..std::cmp::min(limit, MAX_PAYOUTS_PER_DISTRIBUTION) {
+            let (payee, amount) = match self
+                .get_outcome_token(outcome_id)
+                .get_holders(index, 1)
+                .pop()
+            {
+                Some(holder) => holder,
+                None => break,
+            };
+
+            let (amount_payable, _weight) =
+                self.get_amount_payable(amount, outcome_id, payee.clone());
+
+            if amount_payable == 0 {
+                index += 1;
+                continue;
+            }
+
+            // Burn before the transfer so that the holder can't claim twice while it is in flight
+            let mut outcome_token = self.get_outcome_token(outcome_id);
+            outcome_token.burn(&payee, amount);
+            self.outcome_tokens.insert(&outcome_id, &outcome_token);
+            self.update_ct_balance(self.collateral_token.balance - amount_payable);
+
+            log!(
+                "DISTRIBUTE_PAYOUT outcome_id: {}, account_id: {}, amount: {}, amount_payable: {}",
+                outcome_id,
+                payee,
+                amount.to_formatted_string(&FORMATTED_STRING_LOCALE),
+                amount_payable.to_formatted_string(&FORMATTED_STRING_LOCALE),
+            );
+
+            let callback_promise = ext_self::ext(env::current_account_id())
+                .with_attached_deposit(0)
+                .with_static_gas(GAS_DISTRIBUTE_PAYOUT_CALLBACK)
+                .on_distribute_payout_callback(payee.clone(), outcome_id, amount, amount_payable);
+
+            // wNEAR markets are paid out in native NEAR
+            if self.is_collateral_token_wrap_near() {
+                ext_wrap_near::ext(self.collateral_token.id.clone())
+                    .with_attached_deposit(FT_TRANSFER_BOND)
+                    .with_static_gas(GAS_NEAR_WITHDRAW)
+                    .near_withdraw(U128::from(amount_payable))
+                    .then(callback_promise);
+            } else {
+                ext_ft_core::ext(self.collateral_token.id.clone())
+                    .with_attached_deposit(FT_TRANSFER_BOND)
+                    .with_static_gas(GAS_FT_TRANSFER)
+                    .ft_transfer(payee, U128::from(amount_payable), None)
+                    .then(callback_promise);
+            }
+        }
+
+        // Every holder before the cursor is known to be skipped, unless the caller started past it
+        if from_index <= stored_index {
+            self.payout_distribution_index.insert(&outcome_id, &index);
+        }
+
+        index
+    }
+
+    /**
+     * Restores the holder balance if the payout transfer failed, so it may be paid again
+     *
+     * @returns the amount paid
+     */
+    #[private]
+    pub fn on_distribute_payout_callback(
+        &mut self,
+        payee: AccountId,
+        outcome_id: OutcomeId,
+        amount: WrappedBalance,
+        amount_payable: WrappedBalance,
+    ) -> WrappedBalance {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {
+                self.internal_update_position_on_sell(&payee, outcome_id, amount_payable);
+
+                if self.is_collateral_token_wrap_near() {
+                    Promise::new(payee).transfer(amount_payable);
+                }
+
+                amount_payable
+            }
+            // On error, the collateral is still held by the market
+            _ => {
+                let mut outcome_token = self.get_outcome_token(outcome_id);
+                outcome_token.mint(&payee, amount);
+                self.outcome_tokens.insert(&outcome_id, &outcome_token);
+                self.update_ct_balance(self.collateral_token.balance + amount_payable);
+
+                0
+            }
+        }
+    }
+
+    pub fn get_payout_distribution_index(&self, outcome_id: OutcomeId) -> u64 {
+        self.payout_distribution_index.get(&outcome_id).unwrap_or(0)
+    }
+}
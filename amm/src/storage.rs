@@ -1,11 +1,11 @@
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::{LookupMap, UnorderedMap},
+    collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet},
     near_bindgen,
     serde::{Deserialize, Serialize},
     AccountId, BorshStorageKey,
 };
-use shared::{OutcomeId, Price};
+use shared::{OutcomeId, Price, ResolutionEvidence};
 
 pub type Timestamp = i64;
 pub type WrappedBalance = u128;
@@ -20,6 +20,8 @@ pub struct MarketData {
     pub options: Vec<String>,
     // Datetime nanos: the market is open
     pub starts_at: Timestamp,
+    // Datetime nanos: buys are disabled, users should not buy 1 minute before the outcome becomes evident
+    pub close_at: Timestamp,
     // Datetime nanos: the market is closed
     pub ends_at: Timestamp,
     // Keep track of the timezone
@@ -43,6 +45,66 @@ pub struct Market {
     pub outcome_tokens: LookupMap<OutcomeId, OutcomeToken>,
     // If self.price is set, this is a binary yes/no price market — used on self.aggregator_read
     pub price: Option<Pricing>,
+    // Exposure limits enforced on fn buy
+    pub limits: Limits,
+    // Keeps track of the collateral spent by each account, used for limits.max_collateral_per_account
+    pub collateral_spent: LookupMap<AccountId, WrappedBalance>,
+    // If set, this is a restricted market and only allowed accounts may buy
+    pub allow_list: Option<AllowList>,
+    // Set by fn reschedule when the event is postponed, trading is paused until fn resume
+    pub postponed_at: Option<Timestamp>,
+    // Cost basis and realized proceeds of each account per outcome, see fn get_account_positions
+    pub positions: LookupMap<(AccountId, OutcomeId), Position>,
+    // Holders already skipped by fn distribute_payouts per outcome, the index to resume from
+    pub payout_distribution_index: LookupMap<OutcomeId, u64>,
+    // (holder, operator) pairs, approved operators may sell and claim on behalf of the holder
+    pub operators: LookupSet<(AccountId, AccountId)>,
+    // The MarketFactory that deployed this market, the predecessor of fn new
+    pub factory_account_id: AccountId,
+}
+
+// The Market layout written by the previous AMM version, read by fn migrate
+// Set it to the deployed layout before changing the Market layout
+#[derive(BorshDeserialize)]
+pub struct OldMarket {
+    pub market: MarketData,
+    pub collateral_token: CollateralToken,
+    pub fees: Fees,
+    pub resolution: Resolution,
+    pub management: Management,
+    pub outcome_tokens: LookupMap<OutcomeId, OutcomeToken>,
+    pub price: Option<Pricing>,
+    pub limits: Limits,
+    pub collateral_spent: LookupMap<AccountId, WrappedBalance>,
+    pub allow_list: Option<AllowList>,
+    pub postponed_at: Option<Timestamp>,
+    pub positions: LookupMap<(AccountId, OutcomeId), Position>,
+    pub payout_distribution_index: LookupMap<OutcomeId, u64>,
+    pub operators: LookupSet<(AccountId, AccountId)>,
+    pub factory_account_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum MarketStatus {
+    // Outcome tokens are not created or the collateral token is not verified yet
+    Pending,
+    // Buys are enabled before the event starts
+    Presale,
+    // Buys are enabled until the buy/sell timestamp
+    Open,
+    // The event is ongoing, buys are disabled
+    Closed,
+    // The event is over, waiting for fn resolve
+    UnderResolution,
+    // Winners may sell and fees may be claimed
+    Resolved,
+    // Reserved for resolution disputes, not reached by any market yet, clients should handle it
+    Disputed,
+    // The resolution window expired without a resolution or the market was invalidated, holders get their collateral back
+    ExpiredUnresolved,
+    // Resolved and the fees claiming window expired
+    Finalized,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -72,6 +134,14 @@ pub struct CollateralToken {
     pub balance: WrappedBalance,
     pub decimals: u8,
     pub fee_balance: WrappedBalance,
+    // Set from the NEP148 ft_metadata of the collateral token, see fn set_collateral_token_metadata
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    // Buys are refused until the decimals are read from ft_metadata
+    #[serde(default)]
+    pub is_verified: bool,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize)]
@@ -91,6 +161,15 @@ pub struct Resolution {
     pub window: Timestamp,
     // When the market is resolved, set only by fn resolve
     pub resolved_at: Option<Timestamp>,
+    // The winning outcome, set only by fn resolve
+    #[serde(default)]
+    pub winning_outcome_id: Option<OutcomeId>,
+    // The oracle round the market was resolved with, set only by fn resolve when called by the feed parser
+    #[serde(default)]
+    pub evidence: Option<ResolutionEvidence>,
+    // When the market was invalidated, set only by fn invalidate
+    #[serde(default)]
+    pub invalidated_at: Option<Timestamp>,
     // Unit8ByteArray with the immutable Aggregator address, this is the "is_owner" condition to resolve the market
     pub ix: Ix,
 }
@@ -110,11 +189,70 @@ pub struct Pricing {
     pub target_currency_symbol: String,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct Limits {
+    // Max collateral an account may spend in the market, including fees
+    pub max_collateral_per_account: Option<WrappedBalance>,
+    // Max total supply of each outcome token
+    pub max_outcome_token_supply: Option<WrappedBalance>,
+    // Min collateral amount per buy, prevents dust buys
+    pub min_bet_amount: Option<WrappedBalance>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AllowList {
+    // Accounts allowed to participate, managed by the market creator or the DAO
+    pub accounts: UnorderedSet<AccountId>,
+    // Optional external registry, queried by fn ft_on_transfer when the account is not listed
+    pub registry_account_id: Option<AccountId>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AllowListArgs {
+    pub accounts: Vec<AccountId>,
+    pub registry_account_id: Option<AccountId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Default, Clone)]
+pub struct Position {
+    // Collateral spent buying this outcome, including fees
+    pub collateral_spent: WrappedBalance,
+    pub fees_paid: WrappedBalance,
+    // Collateral paid back on fn sell
+    pub proceeds: WrappedBalance,
+}
+
+#[derive(Serialize)]
+pub struct AccountPosition {
+    pub outcome_id: OutcomeId,
+    pub balance: WrappedBalance,
+    pub collateral_spent: WrappedBalance,
+    pub fees_paid: WrappedBalance,
+    // What fn sell would pay for the whole balance right now
+    pub claimable: WrappedBalance,
+    pub realized_proceeds: WrappedBalance,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Claim {
+    pub outcome_id: OutcomeId,
+    // Outcome tokens burnt
+    pub amount: WrappedBalance,
+    pub amount_payable: WrappedBalance,
+}
+
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKeys {
     OutcomeTokens,
     StakingFees,
     MarketCreatorFees,
+    CollateralSpent,
+    AllowList,
+    Positions,
+    PayoutDistributionIndex,
+    Operators,
 }
 
 #[derive(Serialize, Deserialize)]
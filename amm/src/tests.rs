@@ -1,11 +1,14 @@
 #[cfg(test)]
 mod tests {
     use crate::storage::*;
+    use crate::FEED_PARSER_ACCOUNT_ID;
+    use crate::WRAP_NEAR_ACCOUNT_ID;
     use chrono::{Duration, Utc};
     use near_sdk::test_utils::test_env::{alice, bob, carol};
-    use near_sdk::test_utils::VMContextBuilder;
-    use near_sdk::{testing_env, AccountId, Balance, PromiseResult};
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{serde_json::json, testing_env, AccountId, Balance, PromiseResult};
     use rand::seq::SliceRandom;
+    use shared::ResolutionEvidence;
 
     const _ATTACHED_DEPOSIT: Balance = 1_000_000_000_000_000_000_000_000; // 1 Near
 
@@ -65,6 +68,15 @@ mod tests {
     }
 
     fn setup_contract(market: MarketData, res: Option<Resolution>) -> Market {
+        setup_contract_with_options(market, res, None, None)
+    }
+
+    fn setup_contract_with_options(
+        market: MarketData,
+        res: Option<Resolution>,
+        limits: Option<Limits>,
+        allow_list: Option<AllowListArgs>,
+    ) -> Market {
         let ix: Ix = Ix {
             address: IX_ADDRESS,
         };
@@ -73,6 +85,9 @@ mod tests {
             // 3 days
             window: market.ends_at + 259200 * 1_000_000_000,
             resolved_at: None,
+            winning_outcome_id: None,
+            evidence: None,
+            invalidated_at: None,
             ix,
         };
 
@@ -90,12 +105,16 @@ mod tests {
             balance: 0,
             decimals: 6,
             fee_balance: 0,
+            symbol: None,
+            icon: None,
+            is_verified: false,
         };
 
         let fees = Fees {
             staking_fees: None,
             market_creator_fees: None,
-            claiming_window: None,
+            // 30 days
+            claiming_window: Some(resolution.window + 2592000 * 1_000_000_000),
             fee_ratio: LP_FEE,
         };
 
@@ -112,6 +131,8 @@ mod tests {
             collateral_token,
             fees,
             Some(price),
+            limits,
+            allow_list,
         );
 
         contract
@@ -135,7 +156,7 @@ mod tests {
         outcome_id: u64,
         context: &VMContextBuilder,
     ) -> WrappedBalance {
-        let amount_sold = c.sell(outcome_id, amount);
+        let amount_sold = c.sell(outcome_id, amount, None);
 
         testing_env!(
             context.build(),
@@ -166,7 +187,7 @@ mod tests {
             ix.address = inst.address;
         }
 
-        c.resolve(outcome_id, ix);
+        c.resolve(outcome_id, ix, None);
         let balance = *collateral_token_balance;
         *collateral_token_balance -= c.calc_percentage(balance, c.get_fee_ratio());
     }
@@ -175,6 +196,28 @@ mod tests {
         c.create_outcome_tokens();
     }
 
+    fn set_collateral_token_metadata(c: &mut Market, context: &VMContextBuilder) {
+        let metadata = json!({
+            "spec": "ft-1.0.0",
+            "name": "USD Tether",
+            "symbol": "USDT",
+            "icon": null,
+            "reference": null,
+            "reference_hash": null,
+            "decimals": 6,
+        });
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(metadata.to_string().into_bytes())],
+        );
+
+        c.on_ft_metadata_callback();
+    }
+
     fn create_market_data(
         description: String,
         options: u8,
@@ -187,6 +230,8 @@ mod tests {
             category: None,
             options: (0..options).map(|s| s.to_string()).collect(),
             starts_at,
+            // buys are disabled 25% before the event ends
+            close_at: ends_at - (ends_at - starts_at) / 4,
             ends_at,
             utc_offset: -6,
         }
@@ -303,6 +348,7 @@ mod tests {
         let now = ends_at + Duration::days(1);
         testing_env!(context.block_timestamp(block_timestamp(now)).build());
         create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
 
         testing_env!(context
             .block_timestamp(
@@ -416,26 +462,26 @@ mod tests {
 
         // alice sells all her OT balance after the market is resolved
         let alice_balance = contract.balance_of(yes, alice());
-        testing_env!(context.signer_account_id(alice()).build());
+        testing_env!(context.predecessor_account_id(alice()).build());
         sell(&mut contract, alice(), alice_balance, yes, &context);
         let alice_balance = contract.balance_of(yes, alice());
         assert_eq!(alice_balance, 0);
 
         // bob sells his OT balance after the market is resolved. Claim earnings!!
         let bob_balance = contract.balance_of(yes, bob());
-        testing_env!(context.signer_account_id(bob()).build());
+        testing_env!(context.predecessor_account_id(bob()).build());
         sell(&mut contract, bob(), bob_balance, yes, &context);
         let bob_balance = contract.balance_of(yes, bob());
         assert_eq!(bob_balance, 0);
 
         let carol_balance = contract.balance_of(yes, carol());
-        testing_env!(context.signer_account_id(carol()).build());
+        testing_env!(context.predecessor_account_id(carol()).build());
         sell(&mut contract, carol(), carol_balance, yes, &context);
         let carol_balance = contract.balance_of(yes, carol());
         assert_eq!(carol_balance, 0);
 
         let daniel_balance = contract.balance_of(yes, daniel());
-        testing_env!(context.signer_account_id(daniel()).build());
+        testing_env!(context.predecessor_account_id(daniel()).build());
         sell(&mut contract, daniel(), daniel_balance, yes, &context);
         let daniel_balance = contract.balance_of(yes, daniel());
         assert_eq!(daniel_balance, 0);
@@ -473,6 +519,7 @@ mod tests {
 
         let mut contract: Market = setup_contract(market_data, None);
         create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
 
         testing_env!(context
             .block_timestamp(
@@ -507,7 +554,7 @@ mod tests {
 
         // emily tries to sell a losing outcome token
         let emily_balance = contract.balance_of(no, emily());
-        testing_env!(context.signer_account_id(emily()).build());
+        testing_env!(context.predecessor_account_id(emily()).build());
         sell(&mut contract, emily(), emily_balance, no, &context);
     }
 
@@ -537,6 +584,7 @@ mod tests {
         let mut contract: Market = setup_contract(market_data, None);
 
         create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
 
         let amounts = vec![
             100_000_000,
@@ -589,6 +637,7 @@ mod tests {
 
         let mut contract: Market = setup_contract(market_data, None);
         create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
 
         // 25% left to an hour, 46 mins is already closed
         testing_env!(context
@@ -630,6 +679,7 @@ mod tests {
 
         let mut contract: Market = setup_contract(market_data, None);
         create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
 
         buy(
             &mut contract,
@@ -641,7 +691,7 @@ mod tests {
 
         testing_env!(context
             .block_timestamp(block_timestamp(now + Duration::days(4)))
-            .signer_account_id(alice())
+            .predecessor_account_id(alice())
             .build());
         let alice_balance = contract.balance_of(yes, alice());
         sell(&mut contract, alice(), alice_balance, yes, &context);
@@ -653,6 +703,178 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_account_positions_after_buy_and_sell() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        let positions = contract.get_account_positions(alice());
+        assert_eq!(positions[yes as usize].balance, 399_920_000);
+        assert_eq!(positions[yes as usize].collateral_spent, 400_000_000);
+        assert_eq!(positions[yes as usize].fees_paid, 80_000);
+        assert_eq!(positions[no as usize].balance, 0);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(alice())
+            .build());
+        let alice_balance = contract.balance_of(yes, alice());
+        sell(&mut contract, alice(), alice_balance, yes, &context);
+
+        assert_eq!(
+            contract.get_account_positions(alice())[yes as usize].realized_proceeds,
+            399_920_000
+        );
+    }
+
+    #[test]
+    fn get_outcome_holders_paginates_and_prunes_sold_balances() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        for (account_id, amount) in [
+            (alice(), 400_000_000),
+            (bob(), 300_000_000),
+            (carol(), 200_000_000),
+            (daniel(), 100_000_000),
+        ] {
+            buy(
+                &mut contract,
+                &mut collateral_token_balance,
+                account_id,
+                amount,
+                yes,
+            );
+        }
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            emily(),
+            100_000_000,
+            no,
+        );
+
+        assert_eq!(contract.get_holders_count(yes), 4);
+        assert_eq!(
+            contract.get_outcome_holders(yes, 1, 2),
+            vec![(bob(), 299_940_000), (carol(), 199_960_000)]
+        );
+        assert_eq!(contract.get_outcome_holders(yes, 3, u64::MAX).len(), 1);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .build());
+        for account_id in [alice(), bob(), carol(), daniel()] {
+            let balance = contract.balance_of(yes, account_id.clone());
+            testing_env!(context.predecessor_account_id(account_id.clone()).build());
+            sell(&mut contract, account_id, balance, yes, &context);
+        }
+
+        assert_eq!(contract.get_holders_count(yes), 0);
+        assert_eq!(contract.get_outcome_holders(yes, 0, 10), vec![]);
+        assert_eq!(contract.get_holders_count(no), 1);
+    }
+
+    #[test]
+    fn resolve_stores_winning_outcome_and_payouts() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            emily(),
+            100_000_000,
+            no,
+        );
+
+        assert_eq!(contract.get_winning_outcome(), None);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::minutes(1)))
+            .predecessor_account_id(dao_account_id())
+            .build());
+        resolve(&mut contract, &mut collateral_token_balance, yes, None);
+
+        assert_eq!(contract.get_winning_outcome(), Some(yes));
+        assert_eq!(contract.get_payouts(), vec![1_250_000, 0]);
+    }
+
     #[test]
     fn log_aggregator_read() {
         let mut context = setup_context();
@@ -720,6 +942,7 @@ mod tests {
 
         let mut contract: Market = setup_contract(market_data, None);
         create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
 
         buy(
             &mut contract,
@@ -770,4 +993,1957 @@ mod tests {
 
         resolve(&mut contract, &mut collateral_token_balance, yes, Some(ix));
     }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_NOT_FACTORY")]
+    fn set_collateral_token_metadata_error_if_predecessor_is_not_factory() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        contract.set_collateral_token_metadata();
+    }
+
+    #[test]
+    fn set_collateral_token_metadata_from_ft_metadata() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        assert!(!contract.get_collateral_token_metadata().is_verified);
+
+        set_collateral_token_metadata(&mut contract, &context);
+
+        let collateral_token = contract.get_collateral_token_metadata();
+        assert!(collateral_token.is_verified);
+        assert_eq!(collateral_token.decimals, 6);
+        assert_eq!(collateral_token.symbol, Some("USDT".to_string()));
+        assert_eq!(contract.get_precision_decimals(), 1_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_COLLATERAL_TOKEN_NOT_VERIFIED")]
+    fn buy_error_if_collateral_token_is_not_verified() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+    }
+
+    #[test]
+    fn buy_and_sell_with_near() {
+        let mut context = setup_context();
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        contract.collateral_token.id = AccountId::new_unchecked(WRAP_NEAR_ACCOUNT_ID.to_string());
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(context
+            .predecessor_account_id(alice())
+            .attached_deposit(400_000_000)
+            .build());
+        contract.buy_with_near(yes);
+
+        testing_env!(
+            context.attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let amount_mintable = contract.on_near_deposit_callback(alice(), 400_000_000, yes);
+        assert_eq!(contract.balance_of(yes, alice()), amount_mintable);
+        assert_eq!(
+            contract.get_collateral_token_metadata().balance,
+            400_000_000
+        );
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(alice())
+            .build());
+        let amount_payable = contract.sell(yes, amount_mintable, None);
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_near_withdraw_callback(amount_mintable, alice(), yes, amount_payable);
+
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+        assert_eq!(
+            contract.get_collateral_token_metadata().balance,
+            contract.get_collateral_token_metadata().fee_balance
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_COLLATERAL_TOKEN_IS_NOT_WRAP_NEAR")]
+    fn buy_with_near_error_if_collateral_token_is_not_wrap_near() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(context.attached_deposit(400_000_000).build());
+        contract.buy_with_near(0);
+    }
+
+    #[test]
+    fn buy_with_near_refunds_deposit_if_market_closed_before_buy() {
+        let mut context = setup_context();
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        contract.collateral_token.id = AccountId::new_unchecked(WRAP_NEAR_ACCOUNT_ID.to_string());
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(context
+            .predecessor_account_id(alice())
+            .attached_deposit(400_000_000)
+            .build());
+        contract.buy_with_near(yes);
+
+        // The market closed before near_deposit resolved
+        testing_env!(
+            context
+                .attached_deposit(0)
+                .block_timestamp(block_timestamp(ends_at))
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let amount_mintable = contract.on_near_deposit_callback(alice(), 400_000_000, yes);
+
+        assert_eq!(amount_mintable, 0);
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+        assert_eq!(contract.get_collateral_token_metadata().balance, 0);
+    }
+
+    #[test]
+    fn buy_with_near_logs_refund_if_withdraw_fails() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        contract.collateral_token.id = AccountId::new_unchecked(WRAP_NEAR_ACCOUNT_ID.to_string());
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_buy_with_near_refund_callback(alice(), 400_000_000);
+
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("buy_with_near_refund_failure")
+                && log.contains(r#""sender_id":"alice.near""#)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BUY_AMOUNT_LOWER_THAN_MIN_BET_AMOUNT")]
+    fn buy_with_near_error_if_lower_than_min_bet_amount() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let limits = Limits {
+            max_collateral_per_account: None,
+            max_outcome_token_supply: None,
+            min_bet_amount: Some(1_000_000),
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, Some(limits), None);
+        contract.collateral_token.id = AccountId::new_unchecked(WRAP_NEAR_ACCOUNT_ID.to_string());
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(context
+            .predecessor_account_id(alice())
+            .attached_deposit(100)
+            .build());
+        contract.buy_with_near(0);
+    }
+
+    #[test]
+    fn buy_within_limits() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let limits = Limits {
+            max_collateral_per_account: Some(500_000_000),
+            max_outcome_token_supply: Some(1_000_000_000),
+            min_bet_amount: Some(1_000_000),
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, Some(limits), None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            100_000_000,
+            no,
+        );
+
+        assert_eq!(contract.get_collateral_spent(alice()), 500_000_000);
+        assert_eq!(
+            contract.get_limits().max_collateral_per_account,
+            Some(500_000_000)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BUY_EXCEEDS_MAX_COLLATERAL_PER_ACCOUNT")]
+    fn buy_error_if_exceeds_max_collateral_per_account() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let limits = Limits {
+            max_collateral_per_account: Some(500_000_000),
+            ..Default::default()
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, Some(limits), None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            100_000_001,
+            no,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BUY_EXCEEDS_MAX_OUTCOME_TOKEN_SUPPLY")]
+    fn buy_error_if_exceeds_max_outcome_token_supply() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let limits = Limits {
+            max_outcome_token_supply: Some(500_000_000),
+            ..Default::default()
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, Some(limits), None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            bob(),
+            200_000_000,
+            yes,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BUY_AMOUNT_LOWER_THAN_MIN_BET_AMOUNT")]
+    fn buy_error_if_lower_than_min_bet_amount() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let limits = Limits {
+            min_bet_amount: Some(1_000_000),
+            ..Default::default()
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, Some(limits), None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            999,
+            yes,
+        );
+    }
+
+    #[test]
+    fn restricted_market_allow_list() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let allow_list = AllowListArgs {
+            accounts: vec![alice()],
+            registry_account_id: None,
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, None, Some(allow_list));
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        assert!(contract.is_restricted());
+        assert!(contract.is_allowed(alice()));
+        assert!(!contract.is_allowed(bob()));
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(market_creator_account_id())
+            .build());
+        contract.add_to_allow_list(vec![bob(), carol()]);
+        contract.remove_from_allow_list(vec![carol()]);
+
+        assert_eq!(contract.get_allow_list(0, 10), vec![alice(), bob()]);
+        assert_eq!(contract.get_allow_list(1, u64::MAX), vec![bob()]);
+        assert_eq!(contract.get_allow_list_count(), 2);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            bob(),
+            100_000_000,
+            yes,
+        );
+    }
+
+    #[test]
+    fn restricted_market_allow_list_registry() {
+        let mut context = setup_context();
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let allow_list = AllowListArgs {
+            accounts: vec![],
+            registry_account_id: Some(AccountId::new_unchecked("registry.near".to_string())),
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, None, Some(allow_list));
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"false".to_vec())],
+        );
+        let refund = contract.on_allow_list_registry_is_allowed_callback(bob(), 100_000_000, yes);
+        assert_eq!(refund.0, 100_000_000);
+        assert!(!contract.is_allowed(bob()));
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"true".to_vec())],
+        );
+        let refund = contract.on_allow_list_registry_is_allowed_callback(alice(), 100_000_000, yes);
+        assert_eq!(refund.0, 0);
+        assert!(contract.is_allowed(alice()));
+        assert_eq!(contract.balance_of(yes, alice()), 99_980_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ACCOUNT_IS_NOT_ALLOWED")]
+    fn buy_error_if_account_is_not_allowed() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let allow_list = AllowListArgs {
+            accounts: vec![alice()],
+            registry_account_id: None,
+        };
+
+        let mut contract: Market =
+            setup_contract_with_options(market_data, None, None, Some(allow_list));
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            bob(),
+            100_000_000,
+            yes,
+        );
+    }
+
+    #[test]
+    fn market_status_lifecycle() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        assert_eq!(contract.get_status(), MarketStatus::Pending);
+
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+        assert_eq!(contract.get_status(), MarketStatus::Presale);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(starts_at + Duration::minutes(10)))
+            .build());
+        assert_eq!(contract.get_status(), MarketStatus::Open);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(starts_at + Duration::minutes(50)))
+            .build());
+        assert_eq!(contract.get_status(), MarketStatus::Closed);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::hours(1)))
+            .build());
+        assert_eq!(contract.get_status(), MarketStatus::UnderResolution);
+
+        resolve(&mut contract, &mut collateral_token_balance, yes, None);
+        assert_eq!(contract.get_status(), MarketStatus::Resolved);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::days(40)))
+            .build());
+        assert_eq!(contract.get_status(), MarketStatus::Finalized);
+    }
+
+    fn resolve_with_evidence(predecessor_account_id: AccountId) -> Option<ResolutionEvidence> {
+        let mut context = setup_context();
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::hours(1)))
+            .predecessor_account_id(predecessor_account_id)
+            .build());
+        contract.resolve(
+            yes,
+            Ix {
+                address: IX_ADDRESS,
+            },
+            Some(ResolutionEvidence {
+                result: 25_000.0,
+                round_open_timestamp: date(ends_at),
+            }),
+        );
+
+        contract.get_resolution_data().evidence
+    }
+
+    #[test]
+    fn resolve_keeps_evidence_from_feed_parser() {
+        let evidence = resolve_with_evidence(FEED_PARSER_ACCOUNT_ID.parse().unwrap());
+        assert_eq!(evidence.map(|evidence| evidence.result), Some(25_000.0));
+    }
+
+    #[test]
+    fn resolve_drops_evidence_asserted_by_other_callers() {
+        let evidence = resolve_with_evidence(alice());
+        assert!(evidence.is_none());
+    }
+
+    #[test]
+    fn market_status_expired_unresolved() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::days(4)))
+            .build());
+        assert_eq!(contract.get_status(), MarketStatus::ExpiredUnresolved);
+        assert!(contract.is_expired_unresolved());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CLOSE_AT_MUST_NOT_BE_AFTER_ENDS_AT")]
+    fn new_error_if_close_at_is_after_ends_at() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let mut market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+        market_data.close_at = date(ends_at + Duration::minutes(1));
+
+        setup_contract(market_data, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ENDS_AT_MUST_BE_BEFORE_RESOLUTION_WINDOW")]
+    fn new_error_if_resolution_window_is_before_ends_at() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let resolution = Resolution {
+            window: date(ends_at - Duration::minutes(1)),
+            resolved_at: None,
+            winning_outcome_id: None,
+            evidence: None,
+            invalidated_at: None,
+            ix: Ix {
+                address: IX_ADDRESS,
+            },
+        };
+
+        setup_contract(market_data, Some(resolution));
+    }
+
+    #[test]
+    fn finalize_market_after_claiming_window() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::days(40)))
+            .build());
+        contract.finalize();
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"0\"".to_vec())],
+        );
+        contract.on_ft_balance_of_finalize_callback();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CANNOT_FINALIZE_MARKET_BEFORE_CLAIMING_WINDOW_EXPIRATION")]
+    fn finalize_error_if_claiming_window_is_open() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::days(4)))
+            .build());
+        contract.finalize();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ON_FT_BALANCE_OF_FINALIZE_CALLBACK_BALANCE_IS_NOT_0")]
+    fn finalize_error_if_collateral_is_not_swept() {
+        let context = setup_context();
+
+        let now = Utc::now();
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"100\"".to_vec())],
+        );
+        contract.on_ft_balance_of_finalize_callback();
+    }
+
+    #[test]
+    fn update_market_before_trading() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        let mut market_data: MarketData = create_market_data(
+            "a fixed market description".to_string(),
+            3,
+            date(starts_at + Duration::minutes(30)),
+            date(ends_at),
+        );
+        market_data.category = Some("sports".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(market_creator_account_id())
+            .build());
+        contract.update_market(market_data.clone());
+
+        assert_eq!(contract.get_market_data(), market_data);
+        assert_eq!(contract.get_outcome_token(2).total_supply(), 0);
+
+        let market_data: MarketData = create_market_data(
+            "a fixed market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+        contract.update_market(market_data);
+
+        assert_eq!(contract.get_outcome_ids(), vec![0, 1]);
+        assert!(contract.outcome_tokens.get(&2).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UPDATE_MARKET_CLOSE_AT_MUST_BE_IN_THE_FUTURE")]
+    fn update_market_error_if_close_at_is_in_the_past() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(now - Duration::hours(2)),
+            date(now - Duration::hours(1)),
+        );
+
+        testing_env!(context
+            .predecessor_account_id(market_creator_account_id())
+            .build());
+        contract.update_market(market_data);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_HAS_TRADES")]
+    fn update_market_error_if_market_has_trades() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data.clone(), None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            0,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(market_creator_account_id())
+            .build());
+        contract.update_market(market_data);
+    }
+
+    #[test]
+    fn cancel_market_before_trading() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context
+            .predecessor_account_id(market_creator_account_id())
+            .build());
+        contract.cancel();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_NOT_MARKET_CREATOR_OR_FACTORY")]
+    fn cancel_error_if_predecessor_is_not_market_creator() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        contract.cancel();
+    }
+
+    #[test]
+    fn cancel_by_factory() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        // the predecessor of fn new is the factory
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        assert_eq!(contract.get_factory_account_id(), alice());
+
+        testing_env!(context.predecessor_account_id(alice()).build());
+        contract.cancel();
+    }
+
+    #[test]
+    fn reschedule_postponed_event() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        let resolution_window = contract.resolution_window();
+        let claiming_window = contract.claiming_window();
+
+        testing_env!(context.predecessor_account_id(dao_account_id()).build());
+        contract.reschedule(date(ends_at + Duration::days(7)));
+
+        assert!(contract.is_postponed());
+        assert_eq!(contract.get_status(), MarketStatus::Closed);
+        assert_eq!(
+            contract.get_market_data().ends_at,
+            date(ends_at + Duration::days(7))
+        );
+        assert_eq!(
+            contract.resolution_window(),
+            resolution_window + date(now + Duration::days(7)) - date(now)
+        );
+        assert_eq!(
+            contract.claiming_window(),
+            claiming_window + date(now + Duration::days(7)) - date(now)
+        );
+
+        contract.resume();
+        assert_eq!(contract.get_status(), MarketStatus::Presale);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            0,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_IS_POSTPONED")]
+    fn buy_error_if_market_is_postponed() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        testing_env!(context.predecessor_account_id(dao_account_id()).build());
+        contract.reschedule(date(ends_at + Duration::days(1)));
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            0,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESCHEDULE_DELAY_EXCEEDS_MAX")]
+    fn reschedule_error_if_delay_exceeds_max() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context.predecessor_account_id(dao_account_id()).build());
+        contract.reschedule(date(ends_at + Duration::days(91)));
+    }
+
+    #[test]
+    fn claim_all_unresolved_market() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            100_000_000,
+            no,
+        );
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(alice())
+            .build());
+        let amount_payable = contract.claim_all(Some(bob()), None);
+        assert_eq!(amount_payable, 499_900_000);
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+        assert_eq!(contract.balance_of(no, alice()), 0);
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_claim_all_callback(
+            alice(),
+            bob(),
+            vec![
+                Claim {
+                    outcome_id: yes,
+                    amount: 399_920_000,
+                    amount_payable: 399_920_000,
+                },
+                Claim {
+                    outcome_id: no,
+                    amount: 99_980_000,
+                    amount_payable: 99_980_000,
+                },
+            ],
+            amount_payable,
+        );
+
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+        assert_eq!(contract.balance_of(no, alice()), 0);
+        assert_eq!(
+            contract.get_account_positions(alice())[no as usize].realized_proceeds,
+            99_980_000
+        );
+        assert_eq!(
+            contract.get_collateral_token_metadata().balance,
+            contract.get_collateral_token_metadata().fee_balance
+        );
+    }
+
+    #[test]
+    fn claim_all_restores_balances_if_transfer_fails() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        let balance = contract.balance_of(yes, alice());
+        let ct_balance = contract.get_collateral_token_metadata().balance;
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(alice())
+            .build());
+        let amount_payable = contract.claim_all(None, None);
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let amount_paid = contract.on_claim_all_callback(
+            alice(),
+            alice(),
+            vec![Claim {
+                outcome_id: yes,
+                amount: balance,
+                amount_payable,
+            }],
+            amount_payable,
+        );
+
+        assert_eq!(amount_paid, "0");
+        assert_eq!(contract.balance_of(yes, alice()), balance);
+        assert_eq!(contract.get_collateral_token_metadata().balance, ct_balance);
+    }
+
+    #[test]
+    fn sell_restores_balances_if_transfer_fails() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        let balance = contract.balance_of(yes, alice());
+        let ct_balance = contract.get_collateral_token_metadata().balance;
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(alice())
+            .build());
+        let amount_payable = contract.sell(yes, balance, None);
+
+        // Burnt before the transfer, the tokens can't be claimed again while it is pending
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let amount_paid = contract.on_ft_transfer_callback(balance, alice(), yes, amount_payable);
+
+        assert_eq!(amount_paid, "0");
+        assert_eq!(contract.balance_of(yes, alice()), balance);
+        assert_eq!(contract.get_collateral_token_metadata().balance, ct_balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CLAIM_ALL_NOTHING_TO_CLAIM")]
+    fn claim_all_error_if_nothing_to_claim() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(alice())
+            .build());
+        contract.claim_all(None, None);
+    }
+
+    #[test]
+    fn distribute_payouts_resumes_from_cursor() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            bob(),
+            300_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            carol(),
+            100_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            daniel(),
+            200_000_000,
+            no,
+        );
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::hours(1)))
+            .predecessor_account_id(dao_account_id())
+            .build());
+        resolve(&mut contract, &mut collateral_token_balance, yes, None);
+
+        testing_env!(context.predecessor_account_id(emily()).build());
+        assert_eq!(contract.distribute_payouts(yes, None, 2), 0);
+        assert_eq!(contract.get_holders_count(yes), 1);
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+        assert_eq!(contract.balance_of(yes, carol()), 0);
+
+        assert_eq!(contract.distribute_payouts(yes, None, 2), 0);
+        assert_eq!(contract.get_holders_count(yes), 0);
+        assert_eq!(contract.get_outcome_token(yes).total_supply(), 0);
+        assert_eq!(
+            contract.get_collateral_token_metadata().balance,
+            contract.get_collateral_token_metadata().fee_balance
+        );
+
+        // carol's transfer failed, she may be paid again
+        let carol_payout = 199_960_000;
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let amount_paid =
+            contract.on_distribute_payout_callback(carol(), yes, 99_980_000, carol_payout);
+        assert_eq!(amount_paid, 0);
+        assert_eq!(contract.balance_of(yes, carol()), 99_980_000);
+        assert_eq!(contract.get_holders_count(yes), 1);
+    }
+
+    #[test]
+    fn distribute_payouts_pays_holders_moved_behind_a_skipped_one() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+        let no = 1;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+        // Too small a share of the supply to be paid anything while the others hold
+        buy(&mut contract, &mut collateral_token_balance, bob(), 10, yes);
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            carol(),
+            100_000_000,
+            yes,
+        );
+        buy(&mut contract, &mut collateral_token_balance, gus(), 10, yes);
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            daniel(),
+            300_000_000,
+            yes,
+        );
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            emily(),
+            200_000_000,
+            no,
+        );
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::hours(1)))
+            .predecessor_account_id(dao_account_id())
+            .build());
+        resolve(&mut contract, &mut collateral_token_balance, yes, None);
+
+        // alice is paid and replaced by daniel, the last holder, who is paid and replaced by gus
+        testing_env!(context.predecessor_account_id(frank()).build());
+        assert_eq!(contract.distribute_payouts(yes, None, 2), 0);
+        assert_eq!(contract.balance_of(yes, alice()), 0);
+        assert_eq!(contract.balance_of(yes, daniel()), 0);
+
+        // gus and bob are skipped
+        assert_eq!(contract.distribute_payouts(yes, None, 2), 2);
+        assert_ne!(contract.balance_of(yes, gus()), 0);
+        assert_ne!(contract.balance_of(yes, bob()), 0);
+
+        assert_eq!(contract.distribute_payouts(yes, None, 2), 2);
+        assert_eq!(contract.balance_of(yes, carol()), 0);
+        assert_eq!(contract.get_holders_count(yes), 2);
+        assert_eq!(contract.get_payout_distribution_index(yes), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CANT_SELL_A_LOSING_OUTCOME")]
+    fn distribute_payouts_error_if_outcome_is_a_loser() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(ends_at + Duration::hours(1)))
+            .predecessor_account_id(dao_account_id())
+            .build());
+        resolve(&mut contract, &mut collateral_token_balance, 0, None);
+
+        contract.distribute_payouts(1, None, 10);
+    }
+
+    #[test]
+    fn sell_by_approved_operator() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(alice())
+            .attached_deposit(_ATTACHED_DEPOSIT)
+            .build());
+        contract.approve_operator(emily());
+        assert!(contract.is_approved_operator(alice(), emily()));
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(emily())
+            .attached_deposit(0)
+            .build());
+        let amount_payable = contract.sell(yes, 399_920_000, Some(alice()));
+        assert_eq!(amount_payable, 399_920_000);
+
+        testing_env!(context
+            .predecessor_account_id(alice())
+            .attached_deposit(1)
+            .build());
+        contract.revoke_operator(emily());
+        assert!(!contract.is_approved_operator(alice(), emily()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_APPROVE_OPERATOR_REQUIRES_DEPOSIT")]
+    fn approve_operator_error_if_no_deposit() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+
+        testing_env!(context.predecessor_account_id(alice()).build());
+        contract.approve_operator(emily());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CLAIM_ALL_RECEIVER_IS_NOT_HOLDER")]
+    fn claim_all_error_if_operator_sets_receiver() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            0,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(alice())
+            .attached_deposit(_ATTACHED_DEPOSIT)
+            .build());
+        contract.approve_operator(emily());
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(emily())
+            .attached_deposit(0)
+            .build());
+        contract.claim_all(Some(emily()), Some(alice()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_NOT_APPROVED_OPERATOR")]
+    fn sell_error_if_predecessor_is_not_approved_operator() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            0,
+        );
+
+        testing_env!(context
+            .block_timestamp(block_timestamp(now + Duration::days(4)))
+            .predecessor_account_id(frank())
+            .build());
+        contract.sell(0, 399_920_000, Some(alice()));
+    }
+
+    #[test]
+    fn invalidate_market_by_dao() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let yes = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            yes,
+        );
+
+        testing_env!(context.predecessor_account_id(dao_account_id()).build());
+        contract.invalidate();
+        assert_eq!(contract.get_status(), MarketStatus::ExpiredUnresolved);
+
+        testing_env!(context.predecessor_account_id(alice()).build());
+        let alice_balance = contract.balance_of(yes, alice());
+        sell(&mut contract, alice(), alice_balance, yes, &context);
+
+        assert_eq!(
+            contract.get_account_positions(alice())[yes as usize].realized_proceeds,
+            399_920_000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_NOT_DAO")]
+    fn invalidate_error_if_predecessor_is_not_dao() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+        create_outcome_tokens(&mut contract);
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        contract.invalidate();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_NOT_FACTORY")]
+    fn upgrade_error_if_predecessor_is_not_factory() {
+        let mut context = setup_context();
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data, None);
+
+        testing_env!(context
+            .predecessor_account_id(market_creator_account_id())
+            .build());
+        contract.upgrade();
+    }
+
+    #[test]
+    fn migrate_reads_the_previous_layout() {
+        let mut context = setup_context();
+
+        let mut collateral_token_balance: WrappedBalance = 0;
+
+        let now = Utc::now();
+        testing_env!(context.block_timestamp(block_timestamp(now)).build());
+        let starts_at = now + Duration::hours(1);
+        let ends_at = starts_at + Duration::hours(1);
+
+        let market_data: MarketData = create_market_data(
+            "a market description".to_string(),
+            2,
+            date(starts_at),
+            date(ends_at),
+        );
+
+        let mut contract: Market = setup_contract(market_data.clone(), None);
+        create_outcome_tokens(&mut contract);
+        set_collateral_token_metadata(&mut contract, &context);
+
+        buy(
+            &mut contract,
+            &mut collateral_token_balance,
+            alice(),
+            400_000_000,
+            0,
+        );
+
+        near_sdk::env::state_write(&contract);
+
+        let migrated = Market::migrate();
+        assert_eq!(migrated.get_market_data(), market_data);
+        assert_eq!(
+            migrated.balance_of(0, alice()),
+            contract.balance_of(0, alice())
+        );
+        assert_eq!(
+            migrated.get_factory_account_id(),
+            contract.get_factory_account_id()
+        );
+    }
 }
@@ -2,22 +2,8 @@ use crate::math;
 use near_sdk::{env, log, near_bindgen, AccountId};
 use num_format::ToFormattedString;
 use shared::OutcomeId;
-use substring::Substring;
 
-use crate::{storage::*, FORMATTED_STRING_LOCALE};
-
-trait Extract {
-    fn extract_nanoseconds(&self) -> Timestamp;
-}
-
-impl Extract for Timestamp {
-    fn extract_nanoseconds(&self) -> Timestamp {
-        let timestamp = self.to_string();
-        let seconds = timestamp.substring(0, 13);
-
-        seconds.parse::<Timestamp>().unwrap()
-    }
-}
+use crate::{storage::*, FORMATTED_STRING_LOCALE, WRAP_NEAR_ACCOUNT_ID};
 
 #[near_bindgen]
 impl Market {
@@ -36,6 +22,14 @@ impl Market {
         self.resolution.clone()
     }
 
+    pub fn get_limits(&self) -> Limits {
+        self.limits.clone()
+    }
+
+    pub fn get_collateral_spent(&self, account_id: AccountId) -> WrappedBalance {
+        self.collateral_spent.get(&account_id).unwrap_or(0)
+    }
+
     pub fn get_fee_ratio(&self) -> WrappedBalance {
         self.fees.fee_ratio
     }
@@ -47,6 +41,20 @@ impl Market {
         }
     }
 
+    pub fn get_outcome_holders(
+        &self,
+        outcome_id: OutcomeId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(AccountId, WrappedBalance)> {
+        self.get_outcome_token(outcome_id)
+            .get_holders(from_index, limit)
+    }
+
+    pub fn get_holders_count(&self, outcome_id: OutcomeId) -> u64 {
+        self.get_outcome_token(outcome_id).get_holders_count()
+    }
+
     pub fn get_outcome_ids(&self) -> Vec<OutcomeId> {
         self.market
             .options
@@ -60,10 +68,18 @@ impl Market {
         env::block_timestamp().try_into().unwrap()
     }
 
+    pub fn get_factory_account_id(&self) -> AccountId {
+        self.factory_account_id.clone()
+    }
+
     pub fn get_collateral_token_metadata(&self) -> CollateralToken {
         self.collateral_token.clone()
     }
 
+    pub fn is_collateral_token_wrap_near(&self) -> bool {
+        self.collateral_token.id.as_str() == WRAP_NEAR_ACCOUNT_ID
+    }
+
     pub fn get_market_creator_account_id(&self) -> AccountId {
         self.management.market_creator_account_id.clone()
     }
@@ -83,6 +99,46 @@ impl Market {
         }
     }
 
+    pub fn get_winning_outcome(&self) -> Option<OutcomeId> {
+        self.resolution.winning_outcome_id
+    }
+
+    /**
+     * The collateral paid per whole outcome token (10^decimals), indexed by outcome_id
+     * Resolved markets pay only the winning outcome, expired unresolved markets pay 1:1
+     */
+    pub fn get_payouts(&self) -> Vec<WrappedBalance> {
+        let precision = self.get_precision_decimals();
+
+        match self.get_status() {
+            MarketStatus::Resolved | MarketStatus::Finalized => {
+                let ct_balance_minus_fees =
+                    self.collateral_token.balance - self.collateral_token.fee_balance;
+
+                self.get_outcome_ids()
+                    .iter()
+                    .map(|outcome_id| {
+                        let outcome_token = self.get_outcome_token(*outcome_id);
+
+                        if !outcome_token.is_active() || outcome_token.total_supply() == 0 {
+                            return 0;
+                        }
+
+                        math::complex_div_u128(
+                            precision,
+                            ct_balance_minus_fees,
+                            outcome_token.total_supply(),
+                        )
+                    })
+                    .collect()
+            }
+            MarketStatus::ExpiredUnresolved => {
+                self.get_outcome_ids().iter().map(|_| precision).collect()
+            }
+            _ => env::panic_str("ERR_GET_PAYOUTS_MARKET_NOT_RESOLVED"),
+        }
+    }
+
     pub fn is_resolved(&self) -> bool {
         match self.resolution.resolved_at {
             Some(_) => true,
@@ -90,43 +146,126 @@ impl Market {
         }
     }
 
-    pub fn get_buy_sell_timestamp(&self) -> i64 {
-        let diff = (self.market.ends_at - self.market.starts_at) as f64 * 0.25;
+    /**
+     * The market lifecycle, computed in one place and asserted by the modifiers
+     */
+    pub fn get_status(&self) -> MarketStatus {
+        if self.is_resolved() {
+            if self.is_claiming_window_expired() {
+                return MarketStatus::Finalized;
+            }
+
+            return MarketStatus::Resolved;
+        }
+
+        if self.resolution.invalidated_at.is_some() || self.is_resolution_window_expired() {
+            return MarketStatus::ExpiredUnresolved;
+        }
+
+        if !self.is_published() {
+            return MarketStatus::Pending;
+        }
+
+        // Trading is paused while the DAO postpones the event, see fn is_postponed
+        if self.is_postponed() {
+            return MarketStatus::Closed;
+        }
+
+        if self.is_over() {
+            return MarketStatus::UnderResolution;
+        }
+
+        if self.is_open() {
+            if self.is_started() {
+                return MarketStatus::Open;
+            }
+
+            return MarketStatus::Presale;
+        }
 
-        self.market.ends_at - diff as i64
+        MarketStatus::Closed
     }
 
     /**
-     * A market is open (buys are enabled) 3/4 before the event ends
+     * A market is published when its outcome tokens exist and its collateral token is verified
+     */
+    pub fn is_published(&self) -> bool {
+        self.collateral_token.is_verified && self.outcome_tokens.contains_key(&0)
+    }
+
+    pub fn is_postponed(&self) -> bool {
+        self.postponed_at.is_some()
+    }
+
+    pub fn get_buy_sell_timestamp(&self) -> Timestamp {
+        self.market.close_at
+    }
+
+    /**
+     * A market is open (buys are enabled) until market.close_at
      * the reason being that users should not buy 1 minute before the outcome becomes evident
      */
     pub fn is_open(&self) -> bool {
-        let limit = self.get_buy_sell_timestamp();
-
-        self.get_block_timestamp().extract_nanoseconds() <= limit.extract_nanoseconds()
+        self.get_block_timestamp() <= self.market.close_at
     }
 
     pub fn is_closed(&self) -> bool {
         !self.is_open()
     }
 
+    pub fn is_started(&self) -> bool {
+        self.get_block_timestamp() >= self.market.starts_at
+    }
+
     pub fn is_over(&self) -> bool {
-        self.get_block_timestamp().extract_nanoseconds() > self.market.ends_at.extract_nanoseconds()
+        self.get_block_timestamp() > self.market.ends_at
     }
 
     pub fn is_resolution_window_expired(&self) -> bool {
-        self.get_block_timestamp().extract_nanoseconds()
-            > self.resolution.window.extract_nanoseconds()
+        self.get_block_timestamp() > self.resolution.window
     }
 
     pub fn is_expired_unresolved(&self) -> bool {
-        self.is_resolution_window_expired() && !self.is_resolved()
+        self.get_status() == MarketStatus::ExpiredUnresolved
     }
 
     pub fn balance_of(&self, outcome_id: OutcomeId, account_id: AccountId) -> WrappedBalance {
         self.get_outcome_token(outcome_id).get_balance(&account_id)
     }
 
+    /**
+     * The position of account_id in every outcome of the market
+     */
+    pub fn get_account_positions(&self, account_id: AccountId) -> Vec<AccountPosition> {
+        self.get_outcome_ids()
+            .into_iter()
+            .map(|outcome_id| {
+                let position = self
+                    .positions
+                    .get(&(account_id.clone(), outcome_id))
+                    .unwrap_or_default();
+                let outcome_token = self.get_outcome_token(outcome_id);
+                let balance = outcome_token.get_balance(&account_id);
+
+                let claimable = if balance == 0 || !outcome_token.is_active() {
+                    0
+                } else {
+                    self.get_amount_payable(balance, outcome_id, account_id.clone())
+                        .0
+                };
+
+                AccountPosition {
+                    outcome_id,
+                    balance,
+                    collateral_spent: position.collateral_spent,
+                    fees_paid: position.fees_paid,
+                    claimable,
+                    realized_proceeds: position.proceeds,
+                }
+            })
+            .collect()
+    }
+
     pub fn get_amount_mintable(&self, amount: WrappedBalance) -> (WrappedBalance, WrappedBalance) {
         let fee = self.calc_percentage(amount, self.get_fee_ratio());
         let amount_mintable = amount - fee;
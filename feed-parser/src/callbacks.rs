@@ -1,12 +1,12 @@
 use near_sdk::{env, ext_contract, log, near_bindgen, serde_json, PromiseResult::Successful};
 use sbv2_near::AggregatorRound;
-use shared::{OutcomeId, Price};
+use shared::{OutcomeId, Price, ResolutionEvidence};
 
 use crate::storage::*;
 
 #[ext_contract(ext_market)]
 trait Market {
-    fn resolve(&mut self, outcome_id: u64, ix: Ix);
+    fn resolve(&mut self, outcome_id: u64, ix: Ix, evidence: Option<ResolutionEvidence>);
 }
 
 #[near_bindgen]
@@ -42,12 +42,38 @@ impl SwitchboardFeedParser {
                 winning_outcome_id = payload.market_outcome_ids[1];
             }
 
+            let evidence = ResolutionEvidence {
+                result,
+                round_open_timestamp: round.round_open_timestamp,
+            };
+
             // @TODO add a callback for this promise in case it errors
-            ext_market::ext(predecessor_account_id).resolve(winning_outcome_id, payload.ix);
+            ext_market::ext(predecessor_account_id).resolve(
+                winning_outcome_id,
+                payload.ix,
+                Some(evidence),
+            );
 
             return winning_outcome_id;
         }
 
         env::panic_str("ERROR_ON_AGGREGATOR_READ_CALLBACK");
     }
+
+    #[private]
+    pub fn on_latest_price_read_callback(&self) -> ResolutionEvidence {
+        if let Successful(serialized_round) = env::promise_result(0) {
+            let round: AggregatorRound = serde_json::from_slice(&serialized_round).unwrap();
+            let result: Price = round.result.try_into().unwrap();
+
+            log!("on_latest_price_read_callback.result: {:?}", result);
+
+            return ResolutionEvidence {
+                result,
+                round_open_timestamp: round.round_open_timestamp,
+            };
+        }
+
+        env::panic_str("ERROR_ON_LATEST_PRICE_READ_CALLBACK");
+    }
 }
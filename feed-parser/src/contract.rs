@@ -8,6 +8,7 @@ use crate::storage::*;
 #[ext_contract(ext_self)]
 trait Callbacks {
     fn on_internal_above_price_feed_read_callback(&self, payload: AbovePriceFeedArgs);
+    fn on_latest_price_read_callback(&self);
 }
 
 #[near_bindgen]
@@ -25,6 +26,23 @@ impl SwitchboardFeedParser {
             }
         }
     }
+
+    /**
+     * Reads the latest round of an aggregator, used by MarketFactory::create_next_in_series to set the strike price
+     *
+     * @param ix, the aggregator to read
+     *
+     * @returns Promise resolving to the round ResolutionEvidence
+     */
+    pub fn latest_price_read(&self, ix: Ix) -> Promise {
+        let aggregator_read_promise = self.internal_aggregator_read(&ix);
+
+        let on_latest_price_read_callback_promise = ext_self::ext(env::current_account_id())
+            .with_static_gas(GAS_AGGREGATOR_READ_CALLBACK)
+            .on_latest_price_read_callback();
+
+        aggregator_read_promise.then(on_latest_price_read_callback_promise)
+    }
 }
 
 impl SwitchboardFeedParser {
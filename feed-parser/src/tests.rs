@@ -217,4 +217,31 @@ mod tests {
 
         contract.aggregator_read(msg.to_string());
     }
+
+    #[test]
+    fn latest_price_read() {
+        let context = setup_context();
+
+        let contract = SwitchboardFeedParser::default();
+
+        let mut aggregator_round = build_aggregator_round();
+        aggregator_round.result = SwitchboardDecimal::from_f64(PRICE);
+
+        let aggregator_round_bytes = serde_json::to_string(&aggregator_round).unwrap();
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                aggregator_round_bytes.as_bytes().to_vec()
+            )],
+        );
+
+        let evidence = contract.on_latest_price_read_callback();
+
+        assert!((evidence.result - PRICE).abs() < 0.001);
+        assert_eq!(evidence.round_open_timestamp, 1);
+    }
 }
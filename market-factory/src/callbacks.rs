@@ -1,7 +1,10 @@
-use near_sdk::{env, near_bindgen, require, serde_json::json, AccountId, PromiseResult};
+use near_sdk::{
+    env, log, near_bindgen, require, serde_json::json, AccountId, Balance, Promise, PromiseResult,
+};
 
 use crate::consts::*;
 use crate::storage::*;
+use shared::MarketSummaryStatus;
 
 #[near_bindgen]
 impl MarketFactory {
@@ -21,6 +24,14 @@ impl MarketFactory {
                     GAS_FOR_CREATE_OUTCOME_TOKENS,
                 );
 
+                let set_collateral_token_metadata_promise = env::promise_create(
+                    market_account_id.clone(),
+                    "set_collateral_token_metadata",
+                    json!({}).to_string().as_bytes(),
+                    0,
+                    GAS_FOR_SET_COLLATERAL_TOKEN_METADATA,
+                );
+
                 let storage_deposit_promise = env::promise_create(
                     collateral_token_account_id.clone(),
                     "storage_deposit",
@@ -31,8 +42,11 @@ impl MarketFactory {
                     GAS_FOR_FT_STORAGE_DEPOSIT,
                 );
 
-                let promises =
-                    env::promise_and(&[create_outcome_tokens_promise, storage_deposit_promise]);
+                let promises = env::promise_and(&[
+                    create_outcome_tokens_promise,
+                    storage_deposit_promise,
+                    set_collateral_token_metadata_promise,
+                ]);
 
                 let callback = env::promise_then(
                     promises,
@@ -49,8 +63,17 @@ impl MarketFactory {
 
                 (market_account_id, collateral_token_account_id)
             }
-            // @TODO return the attached deposit to the user
-            _ => env::panic_str("ERR_ON_CREATE_MARKET_CALLBACK"),
+            // The market account was not created, the whole deposit was returned to this contract
+            _ => {
+                let deposit = self.get_pending_creation_deposit(&market_account_id);
+                self.internal_fail_creation(
+                    &market_account_id,
+                    deposit,
+                    "ERR_ON_CREATE_MARKET_CALLBACK",
+                );
+
+                (market_account_id, collateral_token_account_id)
+            }
         }
     }
 
@@ -59,24 +82,140 @@ impl MarketFactory {
         &mut self,
         market_account_id: AccountId,
     ) -> bool {
-        require!(env::promise_results_count() == 2);
+        require!(env::promise_results_count() == 3);
 
-        let are_outcome_tokens_created = match env::promise_result(0) {
-            PromiseResult::Successful(_result) => true,
-            _ => env::panic_str("ERR_ON_CREATE_OUTCOME_TOKENS_CALLBACK_0"),
-        };
+        let are_outcome_tokens_created =
+            matches!(env::promise_result(0), PromiseResult::Successful(_result));
 
-        let is_storage_deposit_success = match env::promise_result(1) {
-            PromiseResult::Successful(_result) => true,
-            _ => env::panic_str("ERR_ON_FT_STORAGE_DEPOSIT_CALLBACK_1"),
-        };
+        let is_storage_deposit_success =
+            matches!(env::promise_result(1), PromiseResult::Successful(_result));
+
+        let is_collateral_token_verified =
+            matches!(env::promise_result(2), PromiseResult::Successful(_result));
+
+        if !are_outcome_tokens_created
+            || !is_storage_deposit_success
+            || !is_collateral_token_verified
+        {
+            let reason = if !are_outcome_tokens_created {
+                "ERR_ON_CREATE_OUTCOME_TOKENS_CALLBACK_0"
+            } else if !is_storage_deposit_success {
+                "ERR_ON_FT_STORAGE_DEPOSIT_CALLBACK_1"
+            } else {
+                "ERR_ON_SET_COLLATERAL_TOKEN_METADATA_CALLBACK_2"
+            };
+
+            // The market account holds the rest of the deposit, it is sent to the creator when the account is deleted
+            Promise::new(market_account_id.clone()).function_call(
+                "cancel".to_string(),
+                json!({}).to_string().into_bytes(),
+                0,
+                GAS_FOR_CANCEL_MARKET,
+            );
+
+            // A failed storage_deposit returned its bond to this contract
+            let storage_deposit_refund = if is_storage_deposit_success {
+                0
+            } else {
+                STORAGE_DEPOSIT_BOND
+            };
+
+            // The creation fee is only charged, and the creator bond only locked, once the market is created
+            let refund =
+                storage_deposit_refund + self.get_pending_creation_fee_and_bond(&market_account_id);
+
+            self.internal_fail_creation(&market_account_id, refund, reason);
 
-        if !are_outcome_tokens_created || !is_storage_deposit_success {
             return false;
         }
 
+        if let Some(pending_creation) = self.pending_creations.remove(&market_account_id) {
+            self.collected_creation_fees += pending_creation.creation_fee.0;
+
+            if let Some(creator_bond) = pending_creation.creator_bond {
+                self.creator_bonds.insert(&market_account_id, &creator_bond);
+            }
+        }
+
         self.markets.insert(&market_account_id);
 
+        if let Some(mut market_summary) = self.market_summaries.get(&market_account_id) {
+            market_summary.status = MarketSummaryStatus::Active;
+            self.market_summaries
+                .insert(&market_account_id, &market_summary);
+        }
+
         true
     }
 }
+
+impl MarketFactory {
+    fn get_pending_creation_deposit(&self, market_account_id: &AccountId) -> Balance {
+        self.pending_creations
+            .get(market_account_id)
+            .map(|pending_creation| pending_creation.deposit.0)
+            .unwrap_or(0)
+    }
+
+    fn get_pending_creation_fee_and_bond(&self, market_account_id: &AccountId) -> Balance {
+        self.pending_creations
+            .get(market_account_id)
+            .map(|pending_creation| {
+                pending_creation.creation_fee.0
+                    + pending_creation
+                        .creator_bond
+                        .map(|creator_bond| creator_bond.amount.0)
+                        .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    }
+
+    /**
+     * Refunds the market creator and forgets the pending creation
+     * A FT fee credit used by the creation is given back
+     *
+     * @param refund, the part of the deposit held by this contract
+     * @param reason, the step of the creation that failed
+     */
+    fn internal_fail_creation(
+        &mut self,
+        market_account_id: &AccountId,
+        refund: Balance,
+        reason: &str,
+    ) {
+        let pending_creation = match self.pending_creations.remove(market_account_id) {
+            Some(pending_creation) => pending_creation,
+            None => env::panic_str("ERR_CREATE_MARKET_IS_NOT_PENDING"),
+        };
+
+        // Only the Pending summary inserted by this creation is removed
+        if let Some(market_summary) = self.market_summaries.get(market_account_id) {
+            if market_summary.status == MarketSummaryStatus::Pending {
+                self.market_summaries.remove(market_account_id);
+            }
+        }
+
+        if pending_creation.is_fee_credit_used {
+            self.internal_add_creation_fee_credits(&pending_creation.creator_account_id, 1);
+        }
+
+        if refund > 0 {
+            Promise::new(pending_creation.creator_account_id.clone()).transfer(refund);
+        }
+
+        log!(
+            "EVENT_JSON:{}",
+            json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": "create_market_failure",
+                "data": [{
+                    "market_account_id": market_account_id,
+                    "creator_account_id": pending_creation.creator_account_id,
+                    "refund": refund.to_string(),
+                    "reason": reason,
+                }]
+            })
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use near_sdk::{
+    env, ext_contract, json_types::Base58CryptoHash, json_types::Base64VecU8, log, near_bindgen,
+    serde_json::json, AccountId, Balance, Promise, PromiseResult,
+};
+
+use crate::consts::*;
+use crate::storage::*;
+use shared::MarketSummaryStatus;
+
+#[ext_contract(ext_self)]
+trait Callbacks {
+    fn on_upgrade_market_callback(
+        &mut self,
+        market_account_id: AccountId,
+        code_hash: Base58CryptoHash,
+    ) -> bool;
+}
+
+#[near_bindgen]
+impl MarketFactory {
+    /**
+     * Stores a new AMM code version, markets may then be created with it
+     *
+     * @notice only by the owner, the attached deposit must cover the code storage, the excess is refunded
+     *
+     * @param version, a semver label, eg. 1.2.0
+     * @param code, the AMM wasm
+     *
+     * @returns the sha256 hash of the code
+     */
+    #[payable]
+    pub fn store_code(&mut self, version: String, code: Base64VecU8) -> Base58CryptoHash {
+        self.assert_only_owner();
+
+        if !Self::is_semver(&version) {
+            env::panic_str("ERR_STORE_CODE_INVALID_VERSION");
+        }
+
+        if self
+            .code_versions
+            .values()
+            .any(|code_version| code_version.version == version)
+        {
+            env::panic_str("ERR_STORE_CODE_VERSION_EXISTS");
+        }
+
+        let code_hash: Base58CryptoHash = env::sha256_array(&code.0).into();
+
+        if self.code_versions.get(&code_hash).is_some() {
+            env::panic_str("ERR_STORE_CODE_HASH_EXISTS");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.codes.insert(&code_hash, &code.0);
+        self.code_versions.insert(
+            &code_hash,
+            &CodeVersion {
+                hash: code_hash,
+                version: version.clone(),
+                stored_at: env::block_timestamp(),
+            },
+        );
+
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+
+        if env::attached_deposit() < storage_cost {
+            env::panic_str("ERR_STORE_CODE_INSUFFICIENT_DEPOSIT");
+        }
+
+        if env::attached_deposit() > storage_cost {
+            Promise::new(env::predecessor_account_id())
+                .transfer(env::attached_deposit() - storage_cost);
+        }
+
+        log!(
+            "store_code: version: {}, hash: {}",
+            version,
+            String::from(&code_hash)
+        );
+
+        code_hash
+    }
+
+    /**
+     * Sets the code version fn create_market uses when no code_hash is given
+     *
+     * @notice only by the owner
+     *
+     * @param code_hash, the hash of a stored code version
+     */
+    pub fn set_default_code(&mut self, code_hash: Base58CryptoHash) {
+        self.assert_only_owner();
+
+        if self.code_versions.get(&code_hash).is_none() {
+            env::panic_str("ERR_CODE_HASH_NOT_FOUND");
+        }
+
+        self.default_code_hash = Some(code_hash);
+    }
+
+    /**
+     * Deploys a stored code version to a page of markets, each market deploys it to itself and migrates its state
+     * Results are recorded per market by fn on_upgrade_market_callback
+     *
+     * @notice only by the owner
+     *
+     * @param from_index, the index of the first market in self.markets
+     * @param limit, the max number of markets to read, capped to MAX_MARKETS_PER_UPGRADE, finalized and cancelled markets are skipped
+     * @param code_hash, the hash of a stored code version
+     *
+     * @returns the number of markets being upgraded
+     */
+    pub fn upgrade_markets(
+        &mut self,
+        from_index: u64,
+        limit: u64,
+        code_hash: Base58CryptoHash,
+    ) -> u64 {
+        self.assert_only_owner();
+
+        let code = match self.codes.get(&code_hash) {
+            Some(code) => code,
+            None => env::panic_str("ERR_CODE_HASH_NOT_FOUND"),
+        };
+
+        let markets = self.markets.as_vector();
+        let to_index = std::cmp::min(
+            from_index.saturating_add(std::cmp::min(limit, MAX_MARKETS_PER_UPGRADE)),
+            markets.len(),
+        );
+
+        let mut upgrades_count = 0;
+
+        for index in from_index..to_index {
+            let market_account_id = markets.get(index).unwrap();
+
+            // Finalized and cancelled markets delete their account
+            if let Some(market_summary) = self.market_summaries.get(&market_account_id) {
+                if matches!(
+                    market_summary.status,
+                    MarketSummaryStatus::Finalized | MarketSummaryStatus::Cancelled
+                ) {
+                    continue;
+                }
+            }
+
+            upgrades_count += 1;
+
+            let upgrade_promise = Promise::new(market_account_id.clone()).function_call(
+                "upgrade".to_string(),
+                code.clone(),
+                0,
+                GAS_FOR_UPGRADE_MARKET,
+            );
+
+            let upgrade_callback = ext_self::ext(env::current_account_id())
+                .with_attached_deposit(0)
+                .with_static_gas(GAS_FOR_UPGRADE_MARKET_CALLBACK)
+                .on_upgrade_market_callback(market_account_id, code_hash);
+
+            upgrade_promise.then(upgrade_callback);
+        }
+
+        upgrades_count
+    }
+
+    /**
+     * Records the code version of the upgraded market, or the failed upgrade
+     *
+     * @returns true if the market was upgraded
+     */
+    #[private]
+    pub fn on_upgrade_market_callback(
+        &mut self,
+        market_account_id: AccountId,
+        code_hash: Base58CryptoHash,
+    ) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {
+                self.failed_upgrades.remove(&market_account_id);
+
+                if let Some(mut market_summary) = self.market_summaries.get(&market_account_id) {
+                    market_summary.code_hash = Some(code_hash);
+                    self.market_summaries
+                        .insert(&market_account_id, &market_summary);
+                }
+
+                true
+            }
+            _ => {
+                self.failed_upgrades.insert(&market_account_id, &code_hash);
+
+                log!(
+                    "EVENT_JSON:{}",
+                    json!({
+                        "standard": "pulsemarkets",
+                        "version": "1.0.0",
+                        "event": "upgrade_market_failure",
+                        "data": [{
+                            "market_account_id": market_account_id,
+                            "code_hash": code_hash,
+                        }]
+                    })
+                );
+
+                false
+            }
+        }
+    }
+
+    /**
+     * @returns the markets whose last upgrade failed and the code hash they failed to upgrade to
+     */
+    pub fn get_failed_upgrades(&self) -> Vec<(AccountId, Base58CryptoHash)> {
+        self.failed_upgrades.to_vec()
+    }
+
+    pub fn get_code_versions(&self) -> Vec<CodeVersion> {
+        self.code_versions.values().collect()
+    }
+
+    pub fn get_default_code_hash(&self) -> Option<Base58CryptoHash> {
+        self.default_code_hash
+    }
+
+    /**
+     * @returns the code hash the market was created with, None if it was created with the MARKET_CODE built into the factory
+     */
+    pub fn get_market_code_hash(&self, market_account_id: AccountId) -> Option<Base58CryptoHash> {
+        self.market_summaries
+            .get(&market_account_id)
+            .and_then(|market_summary| market_summary.code_hash)
+    }
+}
+
+impl MarketFactory {
+    /**
+     * @param code_hash, the requested code version, defaults to self.default_code_hash
+     *
+     * @returns the code hash, None for the built-in MARKET_CODE, and the code to deploy
+     */
+    pub fn internal_get_market_code(
+        &self,
+        code_hash: Option<Base58CryptoHash>,
+    ) -> (Option<Base58CryptoHash>, Vec<u8>) {
+        match code_hash.or(self.default_code_hash) {
+            Some(code_hash) => match self.codes.get(&code_hash) {
+                Some(code) => (Some(code_hash), code),
+                None => env::panic_str("ERR_CODE_HASH_NOT_FOUND"),
+            },
+            None => (None, MARKET_CODE.to_vec()),
+        }
+    }
+
+    fn is_semver(version: &str) -> bool {
+        let parts: Vec<&str> = version.split('.').collect();
+
+        parts.len() == 3
+            && parts
+                .iter()
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    }
+}
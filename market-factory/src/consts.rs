@@ -1,4 +1,4 @@
-use near_sdk::{Balance, Gas};
+use near_sdk::{Balance, Gas, ONE_YOCTO};
 
 pub const MARKET_CODE: &[u8] = include_bytes!("../res/amm.wasm");
 
@@ -9,5 +9,38 @@ pub const GAS_FOR_CREATE_MARKET_CALLBACK: Gas = Gas(150_000_000_000_000);
 pub const GAS_FOR_CREATE_OUTCOME_TOKENS: Gas = Gas(15_000_000_000_000);
 pub const GAS_FOR_CREATE_OUTCOME_TOKENS_CALLBACK: Gas = Gas(15_000_000_000_000);
 
+pub const GAS_FOR_SET_COLLATERAL_TOKEN_METADATA: Gas = Gas(15_000_000_000_000);
+
+pub const GAS_FOR_CANCEL_MARKET: Gas = Gas(10_000_000_000_000);
+
+// Deploying the AMM wasm and migrating its state, see amm::Market::upgrade
+pub const GAS_FOR_UPGRADE_MARKET: Gas = Gas(40_000_000_000_000);
+pub const GAS_FOR_UPGRADE_MARKET_CALLBACK: Gas = Gas(5_000_000_000_000);
+// Each upgrade carries the whole AMM wasm, keeps fn upgrade_markets within the 300 TGas limit
+pub const MAX_MARKETS_PER_UPGRADE: u64 = 5;
+
+// NEAR protocol max decimals, keeps the AMM precision math within u128
+pub const MAX_COLLATERAL_TOKEN_DECIMALS: u8 = 24;
+
 pub const STORAGE_DEPOSIT_BOND: Balance = 100_000_000_000_000_000_000_000; // 0.1 NEAR
 pub const GAS_FOR_FT_STORAGE_DEPOSIT: Gas = Gas(15_000_000_000_000);
+
+pub const FT_TRANSFER_BOND: Balance = ONE_YOCTO;
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(5_000_000_000_000);
+pub const GAS_FOR_WITHDRAW_FT_CREATION_FEES_CALLBACK: Gas = Gas(5_000_000_000_000);
+
+// Reading the latest aggregator round, see sbv2_near_feed_parser::latest_price_read
+pub const GAS_FOR_LATEST_PRICE_READ: Gas = Gas(25_000_000_000_000);
+// Covers fn create_market and its callbacks
+pub const GAS_FOR_SERIES_PRICE_READ_CALLBACK: Gas = Gas(200_000_000_000_000);
+
+// 5 minutes, in seconds like the aggregator round_open_timestamp, an older strike price is rejected
+pub const MAX_SERIES_STRIKE_PRICE_AGE: i64 = 300;
+
+pub const FEED_PARSER_V2_MAINNET: &str = "feed-parser.pulsemarkets.near";
+#[cfg(not(near_env = "testnet"))]
+pub const FEED_PARSER_ACCOUNT_ID: &str = FEED_PARSER_V2_MAINNET;
+
+pub const FEED_PARSER_V2_TESTNET: &str = "feed-1.pulsemarkets.testnet";
+#[cfg(near_env = "testnet")]
+pub const FEED_PARSER_ACCOUNT_ID: &str = FEED_PARSER_V2_TESTNET;
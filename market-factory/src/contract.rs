@@ -1,11 +1,14 @@
 use near_sdk::{
-    collections::UnorderedSet, env, ext_contract, json_types::Base64VecU8, near_bindgen,
-    serde_json, serde_json::Value, AccountId, Promise,
+    collections::{LookupMap, UnorderedMap, UnorderedSet},
+    env, ext_contract,
+    json_types::{Base58CryptoHash, Base64VecU8, U128},
+    log, near_bindgen, serde_json, AccountId, Balance, Promise,
 };
 use std::default::Default;
 
 use crate::consts::*;
 use crate::storage::*;
+use shared::{CollateralTokenArgs, CreateMarketArgs, MarketSummaryStatus};
 
 #[ext_contract(ext_self)]
 trait Callbacks {
@@ -25,41 +28,278 @@ impl Default for MarketFactory {
 #[near_bindgen]
 impl MarketFactory {
     #[init]
-    pub fn new() -> Self {
+    pub fn new(owner_id: AccountId) -> Self {
         if env::state_exists() {
             env::panic_str("ERR_ALREADY_INITIALIZED");
         }
 
-        Self {
-            markets: UnorderedSet::new(b"d".to_vec()),
-        }
+        Self::internal_new(owner_id, UnorderedSet::new(b"d".to_vec()))
+    }
+
+    /**
+     * Reads the state written by the factory version deployed before the owner, roles and fees
+     * The markets index is kept, every other collection is created empty
+     *
+     * @notice only by the factory account itself, eg. in the same transaction as the code deployment
+     *
+     * @param owner_id, the factory owner
+     *
+     * @returns the migrated MarketFactory
+     */
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: AccountId) -> Self {
+        let old_state: OldMarketFactory = match env::state_read() {
+            Some(old_state) => old_state,
+            None => env::panic_str("ERR_MIGRATE_STATE_NOT_FOUND"),
+        };
+
+        Self::internal_new(owner_id, old_state.markets)
     }
 
+    /**
+     * Creates and initializes a market sub-account
+     *
+     * @param name, the market sub-account name, {series_id}-* names are reserved for the series rounds
+     * @param args, the JSON CreateMarketArgs
+     * @param code_hash, the stored AMM code version to deploy, defaults to the default code version
+     *
+     * @returns Promise
+     */
     #[payable]
-    pub fn create_market(&mut self, name: AccountId, args: Base64VecU8) -> Promise {
+    pub fn create_market(
+        &mut self,
+        name: AccountId,
+        args: Base64VecU8,
+        code_hash: Option<Base58CryptoHash>,
+    ) -> Promise {
+        self.assert_is_not_paused();
+
+        // The round markets are created by fn create_next_in_series only
+        if self
+            .series
+            .keys()
+            .any(|series_id| name.as_str().starts_with(&format!("{}-", series_id)))
+        {
+            env::panic_str("ERR_CREATE_MARKET_NAME_IS_RESERVED");
+        }
+
         let market_account_id: AccountId = format!("{}.{}", name, env::current_account_id())
             .parse()
             .unwrap();
 
-        let mut init_args: Value = serde_json::from_slice(&args.0.as_slice()).unwrap();
+        let init_args: CreateMarketArgs = match serde_json::from_slice(&args.0.as_slice()) {
+            Ok(init_args) => init_args,
+            Err(_) => env::panic_str("ERR_CREATE_MARKET_INVALID_ARGS"),
+        };
 
-        init_args["management"]["market_creator_account_id"] =
-            Value::String(env::signer_account_id().to_string());
+        self.internal_create_market(
+            market_account_id,
+            init_args,
+            code_hash,
+            env::predecessor_account_id(),
+            env::attached_deposit(),
+        )
+    }
 
-        let collateral_token_account_id: AccountId = init_args["collateral_token"]["id"]
-            .as_str()
-            .unwrap()
-            .parse()
-            .unwrap();
+    /**
+     * Lets a market report its status changes, see amm::Market::internal_notify_factory
+     * The creator bond is returned once the market is resolved, expired or cancelled, and slashed to the market DAO if it invalidates the market
+     *
+     * @notice only by a market created by this factory, a market removed from the index may still settle its bond
+     *
+     * @param status, the new status of the predecessor market
+     */
+    pub fn update_market_status(&mut self, status: MarketSummaryStatus) {
+        let market_account_id = env::predecessor_account_id();
+
+        let creator_bond = self.creator_bonds.get(&market_account_id);
+
+        if !self.markets.contains(&market_account_id) && creator_bond.is_none() {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_A_MARKET");
+        }
+
+        log!(
+            "update_market_status: {}, status: {}",
+            market_account_id,
+            serde_json::to_string(&status).unwrap()
+        );
+
+        if let Some(creator_bond) = creator_bond {
+            match status {
+                MarketSummaryStatus::Resolved
+                | MarketSummaryStatus::Expired
+                | MarketSummaryStatus::Finalized
+                | MarketSummaryStatus::Cancelled => {
+                    self.internal_settle_creator_bond(&market_account_id, creator_bond, false)
+                }
+                MarketSummaryStatus::Invalid => {
+                    self.internal_settle_creator_bond(&market_account_id, creator_bond, true)
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(mut market_summary) = self.market_summaries.get(&market_account_id) {
+            market_summary.status = status;
+            self.market_summaries
+                .insert(&market_account_id, &market_summary);
+        }
+    }
+
+    /**
+     * Lets a market report its rescheduled dates and category, see amm::Market::internal_notify_factory_market_data
+     *
+     * @notice only by a market created by this factory
+     *
+     * @param category, the market category
+     * @param starts_at, datetime nanos the market opens
+     * @param ends_at, datetime nanos the event ends
+     */
+    pub fn update_market_data(&mut self, category: Option<String>, starts_at: i64, ends_at: i64) {
+        let market_account_id = env::predecessor_account_id();
+
+        if !self.markets.contains(&market_account_id) {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_A_MARKET");
+        }
+
+        log!(
+            "update_market_data: {}, starts_at: {}, ends_at: {}",
+            market_account_id,
+            starts_at,
+            ends_at
+        );
+
+        if let Some(mut market_summary) = self.market_summaries.get(&market_account_id) {
+            market_summary.category = category;
+            market_summary.starts_at = starts_at;
+            market_summary.ends_at = ends_at;
+            self.market_summaries
+                .insert(&market_account_id, &market_summary);
+        }
+    }
+}
+
+impl MarketFactory {
+    fn internal_new(owner_id: AccountId, markets: UnorderedSet<AccountId>) -> Self {
+        Self {
+            owner_id,
+            proposed_owner_id: None,
+            roles: UnorderedMap::new(b"r".to_vec()),
+            is_paused: false,
+            markets,
+            pending_creations: LookupMap::new(b"p".to_vec()),
+            market_summaries: LookupMap::new(b"s".to_vec()),
+            codes: LookupMap::new(b"c".to_vec()),
+            code_versions: UnorderedMap::new(b"v".to_vec()),
+            default_code_hash: None,
+            failed_upgrades: UnorderedMap::new(b"f".to_vec()),
+            collateral_tokens: UnorderedMap::new(b"a".to_vec()),
+            daos: UnorderedSet::new(b"o".to_vec()),
+            creation_fee: 0,
+            ft_creation_fees: UnorderedMap::new(b"t".to_vec()),
+            creation_fee_credits: LookupMap::new(b"k".to_vec()),
+            collected_creation_fees: 0,
+            collected_ft_creation_fees: LookupMap::new(b"e".to_vec()),
+            creator_bond: 0,
+            creator_bonds: LookupMap::new(b"b".to_vec()),
+            series: UnorderedMap::new(b"x".to_vec()),
+        }
+    }
+
+    /**
+     * Validates the args, charges the creation fee and creator bond, and deploys the market
+     *
+     * @param market_account_id, the market sub-account
+     * @param init_args, the typed create_market args, the market creator is overwritten
+     * @param code_hash, the stored AMM code version to deploy, defaults to the default code version
+     * @param creator_account_id, the account paying the deposit
+     * @param deposit, the NEAR attached to the market creation
+     *
+     * @returns Promise
+     */
+    pub fn internal_create_market(
+        &mut self,
+        market_account_id: AccountId,
+        mut init_args: CreateMarketArgs,
+        code_hash: Option<Base58CryptoHash>,
+        creator_account_id: AccountId,
+        deposit: Balance,
+    ) -> Promise {
+        if let Some(error) = self.internal_get_create_market_error(
+            &market_account_id,
+            &init_args,
+            code_hash,
+            &creator_account_id,
+            deposit,
+        ) {
+            env::panic_str(error);
+        }
+
+        // A credit is only used when there is a fee to waive
+        let is_fee_credit_used =
+            self.creation_fee > 0 && self.internal_use_creation_fee_credit(&creator_account_id);
+        let creation_fee = if is_fee_credit_used {
+            0
+        } else {
+            self.creation_fee
+        };
+
+        init_args.management.market_creator_account_id = Some(creator_account_id.clone());
+
+        let (code_hash, code) = self.internal_get_market_code(code_hash);
+
+        self.pending_creations.insert(
+            &market_account_id,
+            &PendingCreation {
+                creator_account_id: creator_account_id.clone(),
+                deposit: U128(deposit),
+                creation_fee: U128(creation_fee),
+                is_fee_credit_used,
+                creator_bond: if self.creator_bond > 0 {
+                    Some(CreatorBond {
+                        creator_account_id: creator_account_id.clone(),
+                        dao_account_id: init_args.management.dao_account_id.clone(),
+                        amount: U128(self.creator_bond),
+                    })
+                } else {
+                    None
+                },
+            },
+        );
+
+        self.market_summaries.insert(
+            &market_account_id,
+            &MarketSummary {
+                market_account_id: market_account_id.clone(),
+                category: init_args.market.category.clone(),
+                collateral_token_account_id: init_args.collateral_token.id.clone(),
+                creator_account_id: creator_account_id.clone(),
+                starts_at: init_args.market.starts_at,
+                ends_at: init_args.market.ends_at,
+                base_currency_symbol: init_args
+                    .price
+                    .as_ref()
+                    .map(|price| price.base_currency_symbol.clone()),
+                target_currency_symbol: init_args
+                    .price
+                    .as_ref()
+                    .map(|price| price.target_currency_symbol.clone()),
+                status: MarketSummaryStatus::Pending,
+                code_hash,
+            },
+        );
+
+        let collateral_token_account_id = init_args.collateral_token.id.clone();
 
-        // @TODO if this promise fails, the funds (attached_deposit) are not returned to the signer
+        // If any action fails, the batch is reverted and the deposit is returned to this contract, see fn on_create_market_callback
         let create_market_promise = Promise::new(market_account_id.clone())
             .create_account()
-            .deploy_contract(MARKET_CODE.to_vec())
-            .transfer(env::attached_deposit() - STORAGE_DEPOSIT_BOND)
+            .deploy_contract(code)
+            .transfer(deposit - STORAGE_DEPOSIT_BOND - creation_fee - self.creator_bond)
             .function_call(
                 "new".to_string(),
-                init_args.to_string().into_bytes(),
+                serde_json::to_vec(&init_args).unwrap(),
                 0,
                 GAS_FOR_CREATE_MARKET,
             );
@@ -71,4 +311,143 @@ impl MarketFactory {
 
         create_market_promise.then(create_market_callback)
     }
+
+    /**
+     * Finds the args amm::Market::new would panic on, before any account is created
+     *
+     * @param args, the typed create_market args
+     *
+     * @returns the error of the first invalid arg, if any
+     */
+    fn get_create_market_args_error(&self, args: &CreateMarketArgs) -> Option<&'static str> {
+        let market = &args.market;
+
+        if market.options.len() < 2 {
+            return Some("ERR_CREATE_MARKET_INSUFFICIENT_OPTIONS");
+        }
+
+        if market.starts_at >= market.close_at {
+            return Some("ERR_CREATE_MARKET_STARTS_AT_MUST_BE_BEFORE_CLOSE_AT");
+        }
+
+        if market.close_at > market.ends_at {
+            return Some("ERR_CREATE_MARKET_CLOSE_AT_MUST_NOT_BE_AFTER_ENDS_AT");
+        }
+
+        if market.close_at <= env::block_timestamp() as i64 {
+            return Some("ERR_CREATE_MARKET_CLOSE_AT_MUST_BE_IN_THE_FUTURE");
+        }
+
+        if market.ends_at >= args.resolution.window {
+            return Some("ERR_CREATE_MARKET_ENDS_AT_MUST_BE_BEFORE_RESOLUTION_WINDOW");
+        }
+
+        match args.fees.claiming_window {
+            Some(claiming_window) if claiming_window <= args.resolution.window => {
+                return Some("ERR_CREATE_MARKET_RESOLUTION_WINDOW_MUST_BE_BEFORE_CLAIMING_WINDOW");
+            }
+            None => return Some("ERR_CREATE_MARKET_CLAIMING_WINDOW_NOT_SET"),
+            _ => {}
+        }
+
+        let collateral_token = &args.collateral_token;
+
+        if collateral_token.decimals == 0
+            || collateral_token.decimals > MAX_COLLATERAL_TOKEN_DECIMALS
+        {
+            return Some("ERR_CREATE_MARKET_INVALID_COLLATERAL_TOKEN_DECIMALS");
+        }
+
+        if collateral_token.balance != 0 || collateral_token.fee_balance != 0 {
+            return Some("ERR_CREATE_MARKET_COLLATERAL_TOKEN_BALANCE_MUST_BE_0");
+        }
+
+        // The fee ratio is a percentage with the collateral token precision, see amm::Market::calc_percentage
+        if args.fees.fee_ratio >= 100 * 10_u128.pow(collateral_token.decimals as u32) {
+            return Some("ERR_CREATE_MARKET_INVALID_FEE_RATIO");
+        }
+
+        // The market DAO decides whether the creator bond is slashed, the creator must not pick it
+        if !self.daos.contains(&args.management.dao_account_id) {
+            return Some("ERR_CREATE_MARKET_DAO_NOT_ALLOWED");
+        }
+
+        None
+    }
+
+    /**
+     * @notice the decimals must match the ones verified when the token was allowed
+     *
+     * @param collateral_token, the create_market collateral token args
+     */
+    pub fn assert_is_allowed_collateral_token(&self, collateral_token: &CollateralTokenArgs) {
+        if let Some(error) = self.get_collateral_token_error(collateral_token) {
+            env::panic_str(error);
+        }
+    }
+
+    fn get_collateral_token_error(
+        &self,
+        collateral_token: &CollateralTokenArgs,
+    ) -> Option<&'static str> {
+        match self.collateral_tokens.get(&collateral_token.id) {
+            Some(decimals) if decimals == collateral_token.decimals => None,
+            Some(_) => Some("ERR_CREATE_MARKET_COLLATERAL_TOKEN_DECIMALS_MISMATCH"),
+            None => Some("ERR_CREATE_MARKET_COLLATERAL_TOKEN_NOT_ALLOWED"),
+        }
+    }
+
+    /**
+     * Checks every precondition of fn internal_create_market without panicking
+     *
+     * @param market_account_id, the market sub-account
+     * @param init_args, the typed create_market args
+     * @param code_hash, the stored AMM code version to deploy
+     * @param creator_account_id, the account paying the deposit
+     * @param deposit, the NEAR attached to the market creation
+     *
+     * @returns the error fn internal_create_market would panic with, if any
+     */
+    pub fn internal_get_create_market_error(
+        &self,
+        market_account_id: &AccountId,
+        init_args: &CreateMarketArgs,
+        code_hash: Option<Base58CryptoHash>,
+        creator_account_id: &AccountId,
+        deposit: Balance,
+    ) -> Option<&'static str> {
+        if self.pending_creations.get(market_account_id).is_some() {
+            return Some("ERR_CREATE_MARKET_IS_PENDING");
+        }
+
+        // A failed creation removes the summary, it must never be the summary of another market
+        if self.markets.contains(market_account_id)
+            || self.market_summaries.get(market_account_id).is_some()
+        {
+            return Some("ERR_CREATE_MARKET_ACCOUNT_EXISTS");
+        }
+
+        if let Some(error) = self.get_create_market_args_error(init_args) {
+            return Some(error);
+        }
+
+        if let Some(error) = self.get_collateral_token_error(&init_args.collateral_token) {
+            return Some(error);
+        }
+
+        if deposit
+            < STORAGE_DEPOSIT_BOND
+                + self.get_creation_fee_owed(creator_account_id)
+                + self.creator_bond
+        {
+            return Some("ERR_CREATE_MARKET_INSUFFICIENT_DEPOSIT");
+        }
+
+        match code_hash.or(self.default_code_hash) {
+            Some(code_hash) if !self.codes.contains_key(&code_hash) => {
+                Some("ERR_CODE_HASH_NOT_FOUND")
+            }
+            _ => None,
+        }
+    }
 }
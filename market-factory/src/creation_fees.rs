@@ -0,0 +1,255 @@
+use near_sdk::{
+    env, ext_contract, json_types::U128, log, near_bindgen, serde_json::json, AccountId, Balance,
+    Promise, PromiseOrValue, PromiseResult,
+};
+
+use crate::consts::*;
+use crate::storage::*;
+
+#[ext_contract(ext_self)]
+trait Callbacks {
+    fn on_withdraw_ft_creation_fees_callback(&mut self, token_id: AccountId, amount: U128);
+}
+
+#[ext_contract(ext_ft)]
+trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for MarketFactory {
+    /**
+     * Prepays market creations in a FT accepted with fn set_ft_creation_fee
+     *
+     * @notice a callback function only callable by an accepted FT
+     * @param sender_id, the account credited with the prepaid creations
+     * @param amount, buys as many creations as it covers
+     * @param msg, unused
+     * @returns the amount of tokens that were not spent
+     */
+    #[allow(unused_variables)]
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+
+        let fee = match self.ft_creation_fees.get(&token_id) {
+            Some(fee) => fee.0,
+            None => env::panic_str("ERR_FT_ON_TRANSFER_TOKEN_NOT_ACCEPTED"),
+        };
+
+        let credits = amount.0 / fee;
+
+        if credits == 0 {
+            env::panic_str("ERR_FT_ON_TRANSFER_INSUFFICIENT_AMOUNT");
+        }
+
+        let credits: u32 = match credits.try_into() {
+            Ok(credits) => credits,
+            Err(_) => env::panic_str("ERR_FT_ON_TRANSFER_AMOUNT_TOO_LARGE"),
+        };
+
+        let amount_paid = credits as Balance * fee;
+
+        self.internal_add_creation_fee_credits(&sender_id, credits);
+        self.internal_add_collected_ft_creation_fees(&token_id, amount_paid);
+
+        log!(
+            "EVENT_JSON:{}",
+            json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": "pay_creation_fee",
+                "data": [{
+                    "account_id": sender_id,
+                    "token_id": token_id,
+                    "amount": amount_paid.to_string(),
+                    "credits": credits,
+                }]
+            })
+        );
+
+        PromiseOrValue::Value(U128(amount.0 - amount_paid))
+    }
+}
+
+#[near_bindgen]
+impl MarketFactory {
+    /**
+     * @notice only by a FeeManager
+     *
+     * @param amount, in yocto NEAR, 0 disables the fee
+     */
+    pub fn set_creation_fee(&mut self, amount: U128) {
+        self.assert_has_role(Role::FeeManager);
+
+        self.creation_fee = amount.0;
+
+        log!("set_creation_fee: {}", amount.0);
+    }
+
+    /**
+     * @notice only by a FeeManager
+     *
+     * @param token_id, the FT to accept as creation fee payment
+     * @param amount, the fee in the FT precision, None stops accepting the FT
+     */
+    pub fn set_ft_creation_fee(&mut self, token_id: AccountId, amount: Option<U128>) {
+        self.assert_has_role(Role::FeeManager);
+
+        match amount {
+            Some(amount) if amount.0 > 0 => {
+                self.ft_creation_fees.insert(&token_id, &amount);
+            }
+            Some(_) => env::panic_str("ERR_SET_FT_CREATION_FEE_INVALID_AMOUNT"),
+            None => {
+                self.ft_creation_fees.remove(&token_id);
+            }
+        }
+
+        log!(
+            "set_ft_creation_fee: {}, amount: {:?}",
+            token_id,
+            amount.map(|amount| amount.0)
+        );
+    }
+
+    /**
+     * Sends the collected creation fees
+     *
+     * @notice only by a FeeManager
+     *
+     * @param receiver_id, the account to receive the fees
+     * @param token_id, the FT fees to withdraw, None withdraws the NEAR fees
+     *
+     * @returns Promise
+     */
+    pub fn withdraw_creation_fees(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: Option<AccountId>,
+    ) -> Promise {
+        self.assert_has_role(Role::FeeManager);
+
+        match token_id {
+            Some(token_id) => {
+                let amount = self.get_collected_ft_creation_fees(token_id.clone()).0;
+
+                if amount == 0 {
+                    env::panic_str("ERR_WITHDRAW_CREATION_FEES_NOTHING_TO_WITHDRAW");
+                }
+
+                self.collected_ft_creation_fees.remove(&token_id);
+
+                ext_ft::ext(token_id.clone())
+                    .with_attached_deposit(FT_TRANSFER_BOND)
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .ft_transfer(receiver_id, U128(amount), None)
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_attached_deposit(0)
+                            .with_static_gas(GAS_FOR_WITHDRAW_FT_CREATION_FEES_CALLBACK)
+                            .on_withdraw_ft_creation_fees_callback(token_id, U128(amount)),
+                    )
+            }
+            None => {
+                let amount = self.collected_creation_fees;
+
+                if amount == 0 {
+                    env::panic_str("ERR_WITHDRAW_CREATION_FEES_NOTHING_TO_WITHDRAW");
+                }
+
+                self.collected_creation_fees = 0;
+
+                Promise::new(receiver_id).transfer(amount)
+            }
+        }
+    }
+
+    #[private]
+    pub fn on_withdraw_ft_creation_fees_callback(&mut self, token_id: AccountId, amount: U128) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_result) => {}
+            // The transfer was not made, the fees can be withdrawn again
+            _ => self.internal_add_collected_ft_creation_fees(&token_id, amount.0),
+        }
+    }
+
+    pub fn get_creation_fee(&self) -> U128 {
+        U128(self.creation_fee)
+    }
+
+    pub fn get_ft_creation_fees(&self) -> Vec<(AccountId, U128)> {
+        self.ft_creation_fees.to_vec()
+    }
+
+    pub fn get_creation_fee_credits(&self, account_id: AccountId) -> u32 {
+        self.creation_fee_credits.get(&account_id).unwrap_or(0)
+    }
+
+    pub fn get_collected_creation_fees(&self) -> U128 {
+        U128(self.collected_creation_fees)
+    }
+
+    pub fn get_collected_ft_creation_fees(&self, token_id: AccountId) -> U128 {
+        U128(self.collected_ft_creation_fees.get(&token_id).unwrap_or(0))
+    }
+}
+
+impl MarketFactory {
+    /**
+     * @notice takes one prepaid creation from the account, if it has any
+     *
+     * @returns true if a credit was used
+     */
+    pub fn internal_use_creation_fee_credit(&mut self, account_id: &AccountId) -> bool {
+        let credits = self.get_creation_fee_credits(account_id.clone());
+
+        match credits {
+            0 => false,
+            1 => {
+                self.creation_fee_credits.remove(account_id);
+                true
+            }
+            _ => {
+                self.creation_fee_credits.insert(account_id, &(credits - 1));
+                true
+            }
+        }
+    }
+
+    /**
+     * @returns the NEAR creation fee the account pays for its next market, 0 if a credit waives it
+     */
+    pub fn get_creation_fee_owed(&self, account_id: &AccountId) -> Balance {
+        if self.get_creation_fee_credits(account_id.clone()) > 0 {
+            0
+        } else {
+            self.creation_fee
+        }
+    }
+
+    pub fn internal_add_creation_fee_credits(&mut self, account_id: &AccountId, credits: u32) {
+        let balance = self.get_creation_fee_credits(account_id.clone());
+        self.creation_fee_credits
+            .insert(account_id, &(balance + credits));
+    }
+
+    fn internal_add_collected_ft_creation_fees(&mut self, token_id: &AccountId, amount: Balance) {
+        let balance = self.get_collected_ft_creation_fees(token_id.clone()).0;
+        self.collected_ft_creation_fees
+            .insert(token_id, &(balance + amount));
+    }
+}
@@ -0,0 +1,67 @@
+use near_sdk::{json_types::U128, log, near_bindgen, serde_json::json, AccountId, Promise};
+
+use crate::storage::*;
+
+#[near_bindgen]
+impl MarketFactory {
+    /**
+     * @notice only by a FeeManager, markets already created keep the bond they locked
+     *
+     * @param amount, in yocto NEAR, 0 disables the bond
+     */
+    pub fn set_creator_bond(&mut self, amount: U128) {
+        self.assert_has_role(Role::FeeManager);
+
+        self.creator_bond = amount.0;
+
+        log!("set_creator_bond: {}", amount.0);
+    }
+
+    pub fn get_creator_bond(&self) -> U128 {
+        U128(self.creator_bond)
+    }
+
+    pub fn get_market_creator_bond(&self, market_account_id: AccountId) -> Option<CreatorBond> {
+        self.creator_bonds.get(&market_account_id)
+    }
+}
+
+impl MarketFactory {
+    /**
+     * Returns the bond to the market creator, or slashes it to the market DAO
+     *
+     * @param market_account_id, the market that locked the bond
+     * @param creator_bond, the market bond, removed from the ledger
+     * @param is_slashed, true if the market was invalidated by its DAO
+     */
+    pub fn internal_settle_creator_bond(
+        &mut self,
+        market_account_id: &AccountId,
+        creator_bond: CreatorBond,
+        is_slashed: bool,
+    ) {
+        self.creator_bonds.remove(market_account_id);
+
+        let receiver_id = if is_slashed {
+            creator_bond.dao_account_id
+        } else {
+            creator_bond.creator_account_id
+        };
+
+        Promise::new(receiver_id.clone()).transfer(creator_bond.amount.0);
+
+        log!(
+            "EVENT_JSON:{}",
+            json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": if is_slashed { "slash_creator_bond" } else { "return_creator_bond" },
+                "data": [{
+                    "market_account_id": market_account_id,
+                    "receiver_id": receiver_id,
+                    "amount": creator_bond.amount,
+                }]
+            })
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use near_sdk::{env, log, near_bindgen, serde_json::json, AccountId};
+
+use crate::consts::*;
+use crate::storage::*;
+
+#[near_bindgen]
+impl MarketFactory {
+    /**
+     * Proposes a new owner, the ownership is transferred once the proposed owner accepts it
+     *
+     * @notice only by the owner
+     *
+     * @param owner_id, the proposed owner, None cancels the current proposal
+     */
+    pub fn propose_owner(&mut self, owner_id: Option<AccountId>) {
+        self.assert_only_owner();
+
+        self.proposed_owner_id = owner_id;
+    }
+
+    /**
+     * @notice only by the proposed owner
+     */
+    pub fn accept_ownership(&mut self) {
+        let predecessor_account_id = env::predecessor_account_id();
+
+        if self.proposed_owner_id.as_ref() != Some(&predecessor_account_id) {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_PROPOSED_OWNER");
+        }
+
+        self.internal_log_governance_event(
+            "transfer_ownership",
+            json!({
+                "previous_owner_id": self.owner_id,
+                "owner_id": predecessor_account_id,
+            }),
+        );
+
+        self.owner_id = predecessor_account_id;
+        self.proposed_owner_id = None;
+    }
+
+    /**
+     * @notice only by the owner
+     */
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_only_owner();
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+
+        if !roles.contains(&role) {
+            roles.push(role);
+            self.roles.insert(&account_id, &roles);
+        }
+
+        self.internal_log_governance_event(
+            "grant_role",
+            json!({ "account_id": account_id, "role": role }),
+        );
+    }
+
+    /**
+     * @notice only by the owner
+     */
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_only_owner();
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        roles.retain(|account_role| *account_role != role);
+
+        if roles.is_empty() {
+            self.roles.remove(&account_id);
+        } else {
+            self.roles.insert(&account_id, &roles);
+        }
+
+        self.internal_log_governance_event(
+            "revoke_role",
+            json!({ "account_id": account_id, "role": role }),
+        );
+    }
+
+    /**
+     * Disables fn create_market, existing markets are not affected
+     *
+     * @notice only by a Pauser
+     */
+    pub fn pause(&mut self) {
+        self.assert_has_role(Role::Pauser);
+
+        self.is_paused = true;
+    }
+
+    /**
+     * @notice only by a Pauser
+     */
+    pub fn unpause(&mut self) {
+        self.assert_has_role(Role::Pauser);
+
+        self.is_paused = false;
+    }
+
+    /**
+     * Removes a market from the factory index, the market account itself is not affected
+     *
+     * @notice only by a MarketCurator
+     *
+     * @param market_account_id, the market to delist
+     */
+    pub fn remove_market(&mut self, market_account_id: AccountId) {
+        self.assert_has_role(Role::MarketCurator);
+
+        if !self.markets.remove(&market_account_id) {
+            env::panic_str("ERR_MARKET_NOT_FOUND");
+        }
+
+        self.market_summaries.remove(&market_account_id);
+
+        log!("remove_market: {}", market_account_id);
+    }
+
+    /**
+     * Allows a collateral token in fn create_market
+     *
+     * @notice only by a MarketCurator
+     *
+     * @param token_id, the collateral token
+     * @param decimals, the verified token decimals, create_market args must match them
+     */
+    pub fn add_collateral_token(&mut self, token_id: AccountId, decimals: u8) {
+        self.assert_has_role(Role::MarketCurator);
+
+        if decimals == 0 || decimals > MAX_COLLATERAL_TOKEN_DECIMALS {
+            env::panic_str("ERR_ADD_COLLATERAL_TOKEN_INVALID_DECIMALS");
+        }
+
+        self.collateral_tokens.insert(&token_id, &decimals);
+
+        self.internal_log_governance_event(
+            "add_collateral_token",
+            json!({ "token_id": token_id, "decimals": decimals }),
+        );
+    }
+
+    /**
+     * @notice only by a MarketCurator, existing markets are not affected
+     */
+    pub fn remove_collateral_token(&mut self, token_id: AccountId) {
+        self.assert_has_role(Role::MarketCurator);
+
+        if self.collateral_tokens.remove(&token_id).is_none() {
+            env::panic_str("ERR_COLLATERAL_TOKEN_NOT_FOUND");
+        }
+
+        self.internal_log_governance_event(
+            "remove_collateral_token",
+            json!({ "token_id": token_id }),
+        );
+    }
+
+    pub fn get_collateral_tokens(&self) -> Vec<(AccountId, u8)> {
+        self.collateral_tokens.to_vec()
+    }
+
+    /**
+     * Allows a DAO as market dao_account_id in fn create_market
+     *
+     * @notice only by the owner, the DAO receives the creator bonds it slashes
+     *
+     * @param dao_account_id, the DAO
+     */
+    pub fn add_dao(&mut self, dao_account_id: AccountId) {
+        self.assert_only_owner();
+
+        self.daos.insert(&dao_account_id);
+
+        self.internal_log_governance_event("add_dao", json!({ "dao_account_id": dao_account_id }));
+    }
+
+    /**
+     * @notice only by the owner, existing markets keep their DAO
+     */
+    pub fn remove_dao(&mut self, dao_account_id: AccountId) {
+        self.assert_only_owner();
+
+        if !self.daos.remove(&dao_account_id) {
+            env::panic_str("ERR_DAO_NOT_FOUND");
+        }
+
+        self.internal_log_governance_event(
+            "remove_dao",
+            json!({ "dao_account_id": dao_account_id }),
+        );
+    }
+
+    pub fn get_daos(&self) -> Vec<AccountId> {
+        self.daos.to_vec()
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_proposed_owner(&self) -> Option<AccountId> {
+        self.proposed_owner_id.clone()
+    }
+
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        self.roles.get(&account_id).unwrap_or_default()
+    }
+
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        account_id == self.owner_id || self.get_roles(account_id).contains(&role)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+}
+
+impl MarketFactory {
+    fn internal_log_governance_event(&self, event: &str, data: near_sdk::serde_json::Value) {
+        log!(
+            "EVENT_JSON:{}",
+            json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": event,
+                "data": [data]
+            })
+        );
+    }
+}
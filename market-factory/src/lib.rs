@@ -4,6 +4,24 @@ pub use callbacks::*;
 pub mod consts;
 pub use consts::*;
 
+pub mod governance;
+pub use governance::*;
+
+pub mod modifiers;
+pub use modifiers::*;
+
+pub mod creation_fees;
+pub use creation_fees::*;
+
+pub mod creator_bonds;
+pub use creator_bonds::*;
+
+pub mod series;
+pub use series::*;
+
+pub mod code_registry;
+pub use code_registry::*;
+
 pub mod views;
 pub use views::*;
 
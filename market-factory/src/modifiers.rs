@@ -0,0 +1,24 @@
+use near_sdk::{env, near_bindgen};
+
+use crate::storage::*;
+
+#[near_bindgen]
+impl MarketFactory {
+    pub fn assert_only_owner(&self) {
+        if env::predecessor_account_id() != self.owner_id {
+            env::panic_str("ERR_PREDECESSOR_IS_NOT_OWNER");
+        }
+    }
+
+    pub fn assert_has_role(&self, role: Role) {
+        if !self.has_role(env::predecessor_account_id(), role) {
+            env::panic_str("ERR_PREDECESSOR_IS_MISSING_ROLE");
+        }
+    }
+
+    pub fn assert_is_not_paused(&self) {
+        if self.is_paused {
+            env::panic_str("ERR_FACTORY_IS_PAUSED");
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use near_sdk::{
+    env, ext_contract, log, near_bindgen, serde_json, serde_json::json, AccountId, Promise,
+    PromiseResult,
+};
+
+use crate::consts::*;
+use crate::storage::*;
+use shared::{
+    CollateralTokenArgs, CreateMarketArgs, FeesArgs, Ix, ManagementArgs, MarketDataArgs,
+    PricingArgs, ResolutionArgs, ResolutionEvidence,
+};
+
+#[ext_contract(ext_self)]
+trait Callbacks {
+    fn on_series_price_read_callback(&mut self, series_id: String, creator_account_id: AccountId);
+}
+
+#[ext_contract(ext_feed_parser)]
+trait SwitchboardFeedParser {
+    fn latest_price_read(&self, ix: Ix) -> Promise;
+}
+
+#[near_bindgen]
+impl MarketFactory {
+    /**
+     * Adds a series of recurring price markets
+     *
+     * @notice only by a MarketCurator
+     *
+     * @param series_id, prefixes the round market names, eg. btc-usd-1h
+     * @param template, the args every round is created with
+     */
+    pub fn create_series(&mut self, series_id: String, template: MarketSeriesTemplate) {
+        self.assert_has_role(Role::MarketCurator);
+
+        if self.series.get(&series_id).is_some() {
+            env::panic_str("ERR_CREATE_SERIES_ID_EXISTS");
+        }
+
+        if format!("{}-0.{}", series_id, env::current_account_id())
+            .parse::<AccountId>()
+            .is_err()
+        {
+            env::panic_str("ERR_CREATE_SERIES_INVALID_ID");
+        }
+
+        if template.duration <= 0
+            || template.close_offset < 0
+            || template.close_offset >= template.duration
+        {
+            env::panic_str("ERR_CREATE_SERIES_INVALID_DURATION");
+        }
+
+        if template.resolution_window <= 0 || template.claiming_window <= 0 {
+            env::panic_str("ERR_CREATE_SERIES_INVALID_WINDOWS");
+        }
+
+        self.assert_is_allowed_collateral_token(&CollateralTokenArgs {
+            id: template.collateral_token_account_id.clone(),
+            decimals: template.collateral_token_decimals,
+            balance: 0,
+            fee_balance: 0,
+        });
+
+        if !self.daos.contains(&template.dao_account_id) {
+            env::panic_str("ERR_CREATE_SERIES_DAO_NOT_ALLOWED");
+        }
+
+        if template.fee_ratio >= 100 * 10_u128.pow(template.collateral_token_decimals as u32) {
+            env::panic_str("ERR_CREATE_SERIES_INVALID_FEE_RATIO");
+        }
+
+        self.series.insert(
+            &series_id,
+            &MarketSeries {
+                template,
+                rounds: 0,
+                is_round_pending: false,
+            },
+        );
+
+        log!("create_series: {}", series_id);
+    }
+
+    /**
+     * @notice only by a MarketCurator, not while a round is pending, the markets already created are not affected
+     */
+    pub fn remove_series(&mut self, series_id: String) {
+        self.assert_has_role(Role::MarketCurator);
+
+        match self.series.get(&series_id) {
+            Some(series) if series.is_round_pending => {
+                env::panic_str("ERR_SERIES_ROUND_IS_PENDING")
+            }
+            Some(_) => {
+                self.series.remove(&series_id);
+            }
+            None => env::panic_str("ERR_SERIES_NOT_FOUND"),
+        }
+
+        log!("remove_series: {}", series_id);
+    }
+
+    /**
+     * Creates the next round of a series once the last round has ended
+     * The round starts now and its strike price is the latest aggregator price
+     *
+     * @notice anyone may call it, the caller pays the deposit and becomes the market creator
+     *
+     * @param series_id
+     *
+     * @returns Promise
+     */
+    #[payable]
+    pub fn create_next_in_series(&mut self, series_id: String) -> Promise {
+        self.assert_is_not_paused();
+
+        let mut series = match self.series.get(&series_id) {
+            Some(series) => series,
+            None => env::panic_str("ERR_SERIES_NOT_FOUND"),
+        };
+
+        if series.is_round_pending {
+            env::panic_str("ERR_SERIES_ROUND_IS_PENDING");
+        }
+
+        if series.rounds > 0 {
+            let last_market_account_id =
+                self.get_series_market_account_id(&series_id, series.rounds - 1);
+
+            if let Some(last_market_summary) = self.market_summaries.get(&last_market_account_id) {
+                if last_market_summary.ends_at > env::block_timestamp() as i64 {
+                    env::panic_str("ERR_SERIES_ROUND_NOT_DUE");
+                }
+            }
+        }
+
+        let template = &series.template;
+
+        self.assert_is_allowed_collateral_token(&CollateralTokenArgs {
+            id: template.collateral_token_account_id.clone(),
+            decimals: template.collateral_token_decimals,
+            balance: 0,
+            fee_balance: 0,
+        });
+
+        // Checked again once the price is read, the callback refunds the caller if the round can't be created
+        if env::attached_deposit()
+            < STORAGE_DEPOSIT_BOND
+                + self.get_creation_fee_owed(&env::predecessor_account_id())
+                + self.creator_bond
+        {
+            env::panic_str("ERR_CREATE_MARKET_INSUFFICIENT_DEPOSIT");
+        }
+
+        let ix = template.ix.clone();
+
+        series.is_round_pending = true;
+        self.series.insert(&series_id, &series);
+
+        let latest_price_read_promise =
+            ext_feed_parser::ext(FEED_PARSER_ACCOUNT_ID.to_string().try_into().unwrap())
+                .with_static_gas(GAS_FOR_LATEST_PRICE_READ)
+                .latest_price_read(ix);
+
+        let on_series_price_read_callback = ext_self::ext(env::current_account_id())
+            .with_attached_deposit(env::attached_deposit())
+            .with_static_gas(GAS_FOR_SERIES_PRICE_READ_CALLBACK)
+            .on_series_price_read_callback(series_id, env::predecessor_account_id());
+
+        latest_price_read_promise.then(on_series_price_read_callback)
+    }
+
+    /**
+     * Creates the round market with the strike price read, or refunds the caller and frees the series
+     *
+     * @notice never panics once the price is read, so that the series is not left pending
+     *
+     * @returns true if the round market creation started
+     */
+    #[private]
+    #[payable]
+    pub fn on_series_price_read_callback(
+        &mut self,
+        series_id: String,
+        creator_account_id: AccountId,
+    ) -> bool {
+        let evidence: Option<ResolutionEvidence> = match env::promise_result(0) {
+            PromiseResult::Successful(result) => serde_json::from_slice(&result).ok(),
+            _ => None,
+        };
+
+        let evidence = match evidence {
+            Some(evidence) => evidence,
+            None => {
+                return self.internal_fail_next_in_series(
+                    &series_id,
+                    &creator_account_id,
+                    "ERR_ON_SERIES_PRICE_READ_CALLBACK",
+                )
+            }
+        };
+
+        if evidence.round_open_timestamp
+            < (env::block_timestamp() / 1_000_000_000) as i64 - MAX_SERIES_STRIKE_PRICE_AGE
+        {
+            return self.internal_fail_next_in_series(
+                &series_id,
+                &creator_account_id,
+                "ERR_SERIES_STRIKE_PRICE_IS_STALE",
+            );
+        }
+
+        let mut series = match self.series.get(&series_id) {
+            Some(series) => series,
+            None => {
+                return self.internal_fail_next_in_series(
+                    &series_id,
+                    &creator_account_id,
+                    "ERR_SERIES_NOT_FOUND",
+                )
+            }
+        };
+
+        let round = series.rounds;
+        let market_account_id = self.get_series_market_account_id(&series_id, round);
+        let init_args = Self::get_series_round_args(&series_id, round, &series.template, evidence);
+
+        if let Some(error) = self.internal_get_create_market_error(
+            &market_account_id,
+            &init_args,
+            None,
+            &creator_account_id,
+            env::attached_deposit(),
+        ) {
+            return self.internal_fail_next_in_series(&series_id, &creator_account_id, error);
+        }
+
+        series.rounds += 1;
+        series.is_round_pending = false;
+        self.series.insert(&series_id, &series);
+
+        self.internal_create_market(
+            market_account_id,
+            init_args,
+            None,
+            creator_account_id,
+            env::attached_deposit(),
+        );
+
+        true
+    }
+
+    pub fn get_series(&self, series_id: String) -> Option<MarketSeries> {
+        self.series.get(&series_id)
+    }
+
+    pub fn get_series_ids(&self) -> Vec<String> {
+        self.series.keys_as_vector().to_vec()
+    }
+
+    /**
+     * @notice rounds whose market failed to be created or was removed are skipped
+     *
+     * @param series_id
+     * @param from_index, the first round
+     * @param limit, the max number of rounds to read
+     *
+     * @returns the round market summaries, in round order
+     */
+    pub fn get_series_markets(
+        &self,
+        series_id: String,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<MarketSummary> {
+        let rounds = match self.series.get(&series_id) {
+            Some(series) => series.rounds,
+            None => env::panic_str("ERR_SERIES_NOT_FOUND"),
+        };
+
+        (from_index..std::cmp::min(from_index.saturating_add(limit), rounds))
+            .filter_map(|round| {
+                self.market_summaries
+                    .get(&self.get_series_market_account_id(&series_id, round))
+            })
+            .collect()
+    }
+}
+
+impl MarketFactory {
+    /**
+     * Frees the series for the next call and refunds the deposit attached to fn create_next_in_series
+     *
+     * @param series_id
+     * @param creator_account_id, the caller of fn create_next_in_series
+     * @param reason, the error the round could not be created with
+     *
+     * @returns false
+     */
+    fn internal_fail_next_in_series(
+        &mut self,
+        series_id: &String,
+        creator_account_id: &AccountId,
+        reason: &str,
+    ) -> bool {
+        if let Some(mut series) = self.series.get(series_id) {
+            series.is_round_pending = false;
+            self.series.insert(series_id, &series);
+        }
+
+        Promise::new(creator_account_id.clone()).transfer(env::attached_deposit());
+
+        log!(
+            "EVENT_JSON:{}",
+            json!({
+                "standard": "pulsemarkets",
+                "version": "1.0.0",
+                "event": "create_next_in_series_failure",
+                "data": [{
+                    "series_id": series_id,
+                    "creator_account_id": creator_account_id,
+                    "refund": env::attached_deposit().to_string(),
+                    "reason": reason,
+                }]
+            })
+        );
+
+        false
+    }
+
+    fn get_series_market_account_id(&self, series_id: &str, round: u64) -> AccountId {
+        format!("{}-{}.{}", series_id, round, env::current_account_id())
+            .parse()
+            .unwrap()
+    }
+
+    /**
+     * Builds the create_market args of a round, a yes/no market on the price ending at or below the strike
+     *
+     * @param series_id
+     * @param round, the round number
+     * @param template, the series template
+     * @param evidence, the latest aggregator round, its result is the strike price
+     *
+     * @returns the round CreateMarketArgs
+     */
+    fn get_series_round_args(
+        series_id: &str,
+        round: u64,
+        template: &MarketSeriesTemplate,
+        evidence: ResolutionEvidence,
+    ) -> CreateMarketArgs {
+        let starts_at = env::block_timestamp() as i64;
+        let ends_at = starts_at + template.duration;
+        let resolution_window = ends_at + template.resolution_window;
+
+        CreateMarketArgs {
+            market: MarketDataArgs {
+                description: format!(
+                    "Will the {}/{} price end at or below {}?",
+                    template.base_currency_symbol, template.target_currency_symbol, evidence.result
+                ),
+                info: format!("Round {} of the {} series", round, series_id),
+                category: template.category.clone(),
+                // The feed parser resolves "yes" unless the price ends above the strike
+                options: vec!["yes".to_string(), "no".to_string()],
+                starts_at,
+                close_at: ends_at - template.close_offset,
+                ends_at,
+                utc_offset: 0,
+            },
+            resolution: ResolutionArgs {
+                window: resolution_window,
+                ix: template.ix.clone(),
+            },
+            management: ManagementArgs {
+                dao_account_id: template.dao_account_id.clone(),
+                market_creator_account_id: None,
+            },
+            collateral_token: CollateralTokenArgs {
+                id: template.collateral_token_account_id.clone(),
+                decimals: template.collateral_token_decimals,
+                balance: 0,
+                fee_balance: 0,
+            },
+            fees: FeesArgs {
+                fee_ratio: template.fee_ratio,
+                claiming_window: Some(resolution_window + template.claiming_window),
+            },
+            price: Some(PricingArgs {
+                value: evidence.result,
+                base_currency_symbol: template.base_currency_symbol.clone(),
+                target_currency_symbol: template.target_currency_symbol.clone(),
+            }),
+            limits: None,
+            allow_list: None,
+        }
+    }
+}
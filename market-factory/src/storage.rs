@@ -1,11 +1,214 @@
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::UnorderedSet,
-    near_bindgen, AccountId,
+    collections::{LookupMap, UnorderedMap, UnorderedSet},
+    json_types::{Base58CryptoHash, U128},
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+    AccountId, Balance,
 };
+use shared::{Ix, MarketSummaryStatus};
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct MarketFactory {
+    // Administers the factory and grants roles, ideally the DAO
+    pub owner_id: AccountId,
+    // Set by fn propose_owner, becomes the owner on fn accept_ownership
+    pub proposed_owner_id: Option<AccountId>,
+    pub roles: UnorderedMap<AccountId, Vec<Role>>,
+    // Set by a Pauser, fn create_market is disabled while paused
+    pub is_paused: bool,
     pub markets: UnorderedSet<AccountId>,
+    // Markets being created, keyed by market account, removed once created or refunded
+    pub pending_creations: LookupMap<AccountId, PendingCreation>,
+    // Market metadata stored at creation, lets views filter markets without calling each one
+    pub market_summaries: LookupMap<AccountId, MarketSummary>,
+    // AMM wasm versions uploaded with fn store_code, keyed by sha256 hash
+    pub codes: LookupMap<Base58CryptoHash, Vec<u8>>,
+    pub code_versions: UnorderedMap<Base58CryptoHash, CodeVersion>,
+    // If not set, markets are created with the MARKET_CODE built into the factory
+    pub default_code_hash: Option<Base58CryptoHash>,
+    // Markets whose last fn upgrade_markets failed, and the code hash they failed to upgrade to
+    pub failed_upgrades: UnorderedMap<AccountId, Base58CryptoHash>,
+    // Collateral tokens accepted by fn create_market, and their verified decimals
+    pub collateral_tokens: UnorderedMap<AccountId, u8>,
+    // DAOs accepted as market dao_account_id by fn create_market, they invalidate markets and receive slashed creator bonds
+    pub daos: UnorderedSet<AccountId>,
+    // NEAR paid on top of STORAGE_DEPOSIT_BOND for each fn create_market, 0 disables the fee
+    pub creation_fee: Balance,
+    // FTs accepted as creation fee payment in fn ft_on_transfer, and the fee amount in each token
+    pub ft_creation_fees: UnorderedMap<AccountId, U128>,
+    // Market creations prepaid in a FT, each one waives the NEAR creation fee once
+    pub creation_fee_credits: LookupMap<AccountId, u32>,
+    // Creation fees earned and not yet withdrawn, NEAR and per FT
+    pub collected_creation_fees: Balance,
+    pub collected_ft_creation_fees: LookupMap<AccountId, Balance>,
+    // NEAR locked by the market creator at fn create_market, 0 disables the bond
+    pub creator_bond: Balance,
+    // Bonds of the created markets, removed once the market reports its final status
+    pub creator_bonds: LookupMap<AccountId, CreatorBond>,
+    // Recurring price markets, keyed by series id, see fn create_next_in_series
+    pub series: UnorderedMap<String, MarketSeries>,
+}
+
+// The MarketFactory layout deployed before fn new took an owner_id, read by fn migrate
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldMarketFactory {
+    pub markets: UnorderedSet<AccountId>,
+}
+
+// The owner has every role
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum Role {
+    // Removes spam or broken markets from the factory index
+    MarketCurator,
+    // Manages the factory fees
+    FeeManager,
+    // Pauses and unpauses market creation
+    Pauser,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CodeVersion {
+    pub hash: Base58CryptoHash,
+    // semver label, eg. 1.2.0
+    pub version: String,
+    pub stored_at: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingCreation {
+    pub creator_account_id: AccountId,
+    // The deposit attached to fn create_market, refunded if the creation fails
+    pub deposit: U128,
+    // The part of the deposit charged as creation fee, 0 if a FT fee credit was used
+    pub creation_fee: U128,
+    pub is_fee_credit_used: bool,
+    // Part of the deposit, moved to MarketFactory.creator_bonds once the market is created
+    pub creator_bond: Option<CreatorBond>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreatorBond {
+    pub creator_account_id: AccountId,
+    // The market DAO, receives the bond if it invalidates the market
+    pub dao_account_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketSeries {
+    pub template: MarketSeriesTemplate,
+    // Number of rounds started, round N is the market {series_id}-{N}
+    pub rounds: u64,
+    // Set while the strike price of the next round is read
+    pub is_round_pending: bool,
+}
+
+// The args every round of a series is created with, dates are nanoseconds relative to the round start
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketSeriesTemplate {
+    // Read for the strike price of each round, and to resolve it
+    pub ix: Ix,
+    pub base_currency_symbol: String,
+    pub target_currency_symbol: String,
+    pub category: Option<String>,
+    // From the round start to its end
+    pub duration: i64,
+    // Trading closes this long before the round end
+    pub close_offset: i64,
+    // From the round end to the end of its resolution window
+    pub resolution_window: i64,
+    // From the end of the resolution window to the end of the claiming window
+    pub claiming_window: i64,
+    pub fee_ratio: u128,
+    pub collateral_token_account_id: AccountId,
+    pub collateral_token_decimals: u8,
+    pub dao_account_id: AccountId,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketSummary {
+    pub market_account_id: AccountId,
+    pub category: Option<String>,
+    pub collateral_token_account_id: AccountId,
+    pub creator_account_id: AccountId,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    // Set for price markets, eg. BTC/USD
+    pub base_currency_symbol: Option<String>,
+    pub target_currency_symbol: Option<String>,
+    pub status: MarketSummaryStatus,
+    // None if the market was created with the MARKET_CODE built into the factory
+    pub code_hash: Option<Base58CryptoHash>,
+}
+
+// Every filter that is set must match, see fn get_market_summaries
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketSummaryFilter {
+    pub category: Option<String>,
+    pub creator_account_id: Option<AccountId>,
+    pub collateral_token_account_id: Option<AccountId>,
+    // Markets starting at or after this timestamp
+    pub starts_after: Option<i64>,
+    // Markets ending at or before this timestamp
+    pub ends_before: Option<i64>,
+    pub status: Option<MarketSummaryStatus>,
+    // Set to Some("BTC") to list BTC price markets
+    pub base_currency_symbol: Option<String>,
+}
+
+impl MarketSummaryFilter {
+    pub fn matches(&self, summary: &MarketSummary) -> bool {
+        if self.category.is_some() && self.category != summary.category {
+            return false;
+        }
+
+        if let Some(creator_account_id) = &self.creator_account_id {
+            if *creator_account_id != summary.creator_account_id {
+                return false;
+            }
+        }
+
+        if let Some(collateral_token_account_id) = &self.collateral_token_account_id {
+            if *collateral_token_account_id != summary.collateral_token_account_id {
+                return false;
+            }
+        }
+
+        if let Some(starts_after) = self.starts_after {
+            if summary.starts_at < starts_after {
+                return false;
+            }
+        }
+
+        if let Some(ends_before) = self.ends_before {
+            if summary.ends_at > ends_before {
+                return false;
+            }
+        }
+
+        if let Some(status) = self.status {
+            if status != summary.status {
+                return false;
+            }
+        }
+
+        if self.base_currency_symbol.is_some()
+            && self.base_currency_symbol != summary.base_currency_symbol
+        {
+            return false;
+        }
+
+        true
+    }
 }
@@ -1,11 +1,18 @@
 #[cfg(test)]
 mod tests {
     use crate::consts::STORAGE_DEPOSIT_BOND;
-    use crate::storage::MarketFactory;
+    use crate::creation_fees::FungibleTokenReceiver;
+    use crate::storage::{
+        MarketFactory, MarketSeriesTemplate, MarketSummaryFilter, OldMarketFactory, Role,
+    };
     use chrono::{Duration, Utc};
-    use near_sdk::test_utils::test_env::alice;
-    use near_sdk::test_utils::VMContextBuilder;
-    use near_sdk::{serde_json::json, testing_env, AccountId};
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::test_env::{alice, bob, carol};
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{
+        serde_json::json, serde_json::Value, testing_env, AccountId, PromiseOrValue, PromiseResult,
+    };
+    use shared::MarketSummaryStatus;
 
     const IX_ADDRESS: [u8; 32] = [
         173, 62, 255, 125, 45, 251, 162, 167, 128, 129, 25, 33, 146, 248, 118, 134, 118, 192, 215,
@@ -25,7 +32,9 @@ mod tests {
     }
 
     fn setup_contract() -> MarketFactory {
-        let contract = MarketFactory::new();
+        let mut contract = MarketFactory::new(alice());
+        contract.add_collateral_token(collateral_token_account_id(), 6);
+        contract.add_dao(dao_account_id());
         contract
     }
 
@@ -33,34 +42,41 @@ mod tests {
         date.timestamp_nanos().try_into().unwrap()
     }
 
-    #[test]
-    fn create_market() {
-        setup_context();
+    fn market_account_id() -> AccountId {
+        format!("480c9dbe-a5ec.{}", near_sdk::env::current_account_id())
+            .parse()
+            .unwrap()
+    }
+
+    fn dao_account_id() -> AccountId {
+        AccountId::new_unchecked("dao-account-id.near".to_string())
+    }
 
+    fn collateral_token_account_id() -> AccountId {
+        AccountId::new_unchecked("collateral-token-account-id.near".to_string())
+    }
+
+    fn create_market_args() -> Value {
         let now = Utc::now();
         let starts_at = now + Duration::hours(1);
         let ends_at = starts_at + Duration::hours(1);
 
-        let mut contract = setup_contract();
-
-        let name = AccountId::new_unchecked("480c9dbe-a5ec".to_string());
-        let dao_account_id = AccountId::new_unchecked("dao-account-id.near".to_string());
+        let dao_account_id = dao_account_id();
         let market_creator_account_id =
             AccountId::new_unchecked("market-creator-account-id.near".to_string());
-        let collateral_token_account_id =
-            AccountId::new_unchecked("collateral-token-account-id.near".to_string());
 
-        let args = json!({
+        json!({
             "market": {
                 "description": "description",
                 "info": "info",
                 "options": vec!["option_1", "option_2"],
-                "starts_at": date(starts_at).to_string(),
-                "ends_at": date(ends_at).to_string(),
+                "starts_at": date(starts_at),
+                "close_at": date(ends_at - Duration::minutes(15)),
+                "ends_at": date(ends_at),
                 "utc_offset": 0,
             },
             "resolution": {
-                "window": date(now).to_string(),
+                "window": date(ends_at + Duration::days(3)),
                 "ix": {
                     "address": IX_ADDRESS.to_vec()
                 },
@@ -70,16 +86,878 @@ mod tests {
                 "market_creator_account_id": market_creator_account_id,
             },
             "collateral_token": {
-                "id": collateral_token_account_id,
+                "id": collateral_token_account_id(),
                 "balance": 0,
                 "decimals": 6,
                 "fee_balance": 0,
             },
             "fees": {
                 "fee_ratio": 20_000,
+                "claiming_window": date(ends_at + Duration::days(33)),
             },
+        })
+    }
+
+    fn series_template() -> MarketSeriesTemplate {
+        near_sdk::serde_json::from_value(json!({
+            "ix": {
+                "address": IX_ADDRESS.to_vec()
+            },
+            "base_currency_symbol": "BTC",
+            "target_currency_symbol": "USD",
+            "category": "crypto",
+            "duration": Duration::hours(1).num_nanoseconds().unwrap(),
+            "close_offset": Duration::minutes(15).num_nanoseconds().unwrap(),
+            "resolution_window": Duration::days(3).num_nanoseconds().unwrap(),
+            "claiming_window": Duration::days(30).num_nanoseconds().unwrap(),
+            "fee_ratio": 20_000,
+            "collateral_token_account_id": collateral_token_account_id(),
+            "collateral_token_decimals": 6,
+            "dao_account_id": "dao-account-id.near",
+        }))
+        .unwrap()
+    }
+
+    fn series_market_account_id(round: u64) -> AccountId {
+        format!(
+            "btc-usd-1h-{}.{}",
+            round,
+            near_sdk::env::current_account_id()
+        )
+        .parse()
+        .unwrap()
+    }
+
+    fn call_create_market(contract: &mut MarketFactory, args: Value) {
+        call_create_market_with_name(contract, "480c9dbe-a5ec", args);
+    }
+
+    fn call_create_market_with_name(contract: &mut MarketFactory, name: &str, args: Value) {
+        let name = AccountId::new_unchecked(name.to_string());
+
+        contract.create_market(name, args.to_string().into_bytes().to_vec().into(), None);
+    }
+
+    fn complete_create_market(
+        contract: &mut MarketFactory,
+        context: &VMContextBuilder,
+        market_account_id: AccountId,
+    ) {
+        set_promise_results(
+            context,
+            vec![
+                PromiseResult::Successful(vec![]),
+                PromiseResult::Successful(vec![]),
+                PromiseResult::Successful(vec![]),
+            ],
+        );
+        contract.on_create_outcome_tokens_ft_storage_deposit_callback(market_account_id);
+    }
+
+    fn set_promise_results(context: &VMContextBuilder, promise_results: Vec<PromiseResult>) {
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            promise_results,
+        );
+    }
+
+    #[test]
+    fn create_market() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+
+        let pending_creation = contract.get_pending_creation(market_account_id()).unwrap();
+        assert_eq!(pending_creation.creator_account_id, alice());
+        assert_eq!(pending_creation.deposit, U128(STORAGE_DEPOSIT_BOND * 2));
+
+        set_promise_results(&context, vec![PromiseResult::Successful(vec![])]);
+        contract.on_create_market_callback(market_account_id(), collateral_token_account_id());
+
+        set_promise_results(
+            &context,
+            vec![
+                PromiseResult::Successful(vec![]),
+                PromiseResult::Successful(vec![]),
+                PromiseResult::Successful(vec![]),
+            ],
+        );
+        let is_created =
+            contract.on_create_outcome_tokens_ft_storage_deposit_callback(market_account_id());
+
+        assert!(is_created);
+        assert_eq!(contract.get_markets_list(), vec![market_account_id()]);
+        assert!(contract.get_pending_creation(market_account_id()).is_none());
+        assert_eq!(
+            contract
+                .get_market_summary(market_account_id())
+                .unwrap()
+                .status,
+            MarketSummaryStatus::Active
+        );
+    }
+
+    #[test]
+    fn create_market_refunds_deposit_if_create_account_fails() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        contract.on_create_market_callback(market_account_id(), collateral_token_account_id());
+
+        assert!(contract.get_pending_creation(market_account_id()).is_none());
+        assert_eq!(contract.get_markets_count(), 0);
+    }
+
+    #[test]
+    fn create_market_refunds_deposit_if_storage_deposit_fails() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+
+        set_promise_results(
+            &context,
+            vec![
+                PromiseResult::Successful(vec![]),
+                PromiseResult::Failed,
+                PromiseResult::Successful(vec![]),
+            ],
+        );
+        let is_created =
+            contract.on_create_outcome_tokens_ft_storage_deposit_callback(market_account_id());
+
+        assert!(!is_created);
+        assert!(contract.get_pending_creation(market_account_id()).is_none());
+        assert!(contract.get_market_summary(market_account_id()).is_none());
+        assert_eq!(contract.get_markets_count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_IS_PENDING")]
+    fn create_market_error_if_creation_is_pending() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+        call_create_market(&mut contract, create_market_args());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_ACCOUNT_EXISTS")]
+    fn create_market_error_if_market_exists() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        testing_env!(context.build());
+        call_create_market(&mut contract, create_market_args());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_INSUFFICIENT_OPTIONS")]
+    fn create_market_error_if_options_are_insufficient() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        let mut args = create_market_args();
+        args["market"]["options"] = json!(vec!["option_1"]);
+
+        call_create_market(&mut contract, args);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_INVALID_FEE_RATIO")]
+    fn create_market_error_if_fee_ratio_is_invalid() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        let mut args = create_market_args();
+        args["fees"]["fee_ratio"] = json!(100_000_000);
+
+        call_create_market(&mut contract, args);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_INVALID_ARGS")]
+    fn create_market_error_if_args_are_malformed() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        let mut args = create_market_args();
+        args["collateral_token"] = json!({});
+
+        call_create_market(&mut contract, args);
+    }
+
+    #[test]
+    fn get_market_summaries_with_filters() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+
+        let mut crypto_args = create_market_args();
+        crypto_args["market"]["category"] = json!("crypto");
+        crypto_args["price"] = json!({
+            "value": 25_000.0,
+            "base_currency_symbol": "BTC",
+            "target_currency_symbol": "USD",
         });
+        call_create_market_with_name(&mut contract, "crypto", crypto_args);
+
+        testing_env!(context.build());
+        let mut sports_args = create_market_args();
+        sports_args["market"]["category"] = json!("sports");
+        call_create_market_with_name(&mut contract, "sports", sports_args);
+
+        let crypto_market_account_id: AccountId =
+            format!("crypto.{}", near_sdk::env::current_account_id())
+                .parse()
+                .unwrap();
+        let sports_market_account_id: AccountId =
+            format!("sports.{}", near_sdk::env::current_account_id())
+                .parse()
+                .unwrap();
+
+        // Markets are listed once created
+        assert!(contract.get_market_summaries(None, 0, 10).is_empty());
+
+        complete_create_market(&mut contract, &context, crypto_market_account_id.clone());
+        complete_create_market(&mut contract, &context, sports_market_account_id.clone());
+
+        assert_eq!(contract.get_market_summaries(None, 0, 10).len(), 2);
+        assert_eq!(contract.get_market_summaries(None, 1, u64::MAX).len(), 1);
+
+        let crypto_markets = contract.get_market_summaries(
+            Some(MarketSummaryFilter {
+                category: Some("crypto".to_string()),
+                base_currency_symbol: Some("BTC".to_string()),
+                ..Default::default()
+            }),
+            0,
+            10,
+        );
+        assert_eq!(crypto_markets.len(), 1);
+        assert_eq!(
+            crypto_markets[0].market_account_id,
+            crypto_market_account_id
+        );
+
+        // Pages are read from the markets index, a page may hold fewer matches than the limit
+        let sports_markets = contract.get_market_summaries(
+            Some(MarketSummaryFilter {
+                category: Some("sports".to_string()),
+                ..Default::default()
+            }),
+            0,
+            1,
+        );
+        assert!(sports_markets.is_empty());
+
+        let alice_markets = contract.get_market_summaries(
+            Some(MarketSummaryFilter {
+                creator_account_id: Some(alice()),
+                collateral_token_account_id: Some(collateral_token_account_id()),
+                status: Some(MarketSummaryStatus::Active),
+                ..Default::default()
+            }),
+            0,
+            10,
+        );
+        assert_eq!(alice_markets.len(), 2);
+
+        let ended_markets = contract.get_market_summaries(
+            Some(MarketSummaryFilter {
+                ends_before: Some(date(Utc::now())),
+                ..Default::default()
+            }),
+            0,
+            10,
+        );
+        assert!(ended_markets.is_empty());
+    }
+
+    #[test]
+    fn update_market_status_by_market() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        testing_env!(context.predecessor_account_id(market_account_id()).build());
+        contract.update_market_status(MarketSummaryStatus::Resolved);
+
+        let resolved_markets = contract.get_market_summaries(
+            Some(MarketSummaryFilter {
+                status: Some(MarketSummaryStatus::Resolved),
+                ..Default::default()
+            }),
+            0,
+            10,
+        );
+        assert_eq!(resolved_markets.len(), 1);
+    }
+
+    #[test]
+    fn update_market_data_by_market() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        let starts_at = date(Utc::now() + Duration::days(1));
+        let ends_at = date(Utc::now() + Duration::days(2));
+
+        testing_env!(context.predecessor_account_id(market_account_id()).build());
+        contract.update_market_data(Some("sports".to_string()), starts_at, ends_at);
+
+        let market_summary = contract.get_market_summary(market_account_id()).unwrap();
+        assert_eq!(market_summary.category, Some("sports".to_string()));
+        assert_eq!(market_summary.starts_at, starts_at);
+        assert_eq!(market_summary.ends_at, ends_at);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_NOT_A_MARKET")]
+    fn update_market_status_error_if_predecessor_is_not_a_market() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        contract.update_market_status(MarketSummaryStatus::Resolved);
+    }
+
+    #[test]
+    fn create_market_with_stored_code_version() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        let code_hash = contract.store_code("1.1.0".to_string(), b"amm-1.1.0".to_vec().into());
+        contract.set_default_code(code_hash);
+
+        assert_eq!(contract.get_code_versions()[0].version, "1.1.0".to_string());
+        assert_eq!(contract.get_default_code_hash(), Some(code_hash));
+
+        call_create_market(&mut contract, create_market_args());
+
+        assert_eq!(
+            contract.get_market_code_hash(market_account_id()),
+            Some(code_hash)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STORE_CODE_INVALID_VERSION")]
+    fn store_code_error_if_version_is_not_semver() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        contract.store_code("v1".to_string(), b"amm-v1".to_vec().into());
+    }
+
+    #[test]
+    fn upgrade_markets_records_code_hash_and_failures() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+
+        call_create_market(&mut contract, create_market_args());
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        testing_env!(context.build());
+        let code_hash = contract.store_code("1.1.0".to_string(), b"amm-1.1.0".to_vec().into());
+
+        assert_eq!(contract.upgrade_markets(0, 10, code_hash), 1);
+
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        assert!(!contract.on_upgrade_market_callback(market_account_id(), code_hash));
+        assert_eq!(
+            contract.get_failed_upgrades(),
+            vec![(market_account_id(), code_hash)]
+        );
+        assert_eq!(contract.get_market_code_hash(market_account_id()), None);
+
+        set_promise_results(&context, vec![PromiseResult::Successful(vec![])]);
+        assert!(contract.on_upgrade_market_callback(market_account_id(), code_hash));
+        assert!(contract.get_failed_upgrades().is_empty());
+        assert_eq!(
+            contract.get_market_code_hash(market_account_id()),
+            Some(code_hash)
+        );
+
+        // The finalized market account is deleted, it is not upgraded again
+        testing_env!(context
+            .clone()
+            .predecessor_account_id(market_account_id())
+            .build());
+        contract.update_market_status(MarketSummaryStatus::Finalized);
+
+        testing_env!(context.build());
+        assert_eq!(contract.upgrade_markets(0, 10, code_hash), 0);
+    }
+
+    #[test]
+    fn migrate_keeps_the_markets_index() {
+        setup_context();
+
+        let mut old_state = OldMarketFactory {
+            markets: near_sdk::collections::UnorderedSet::new(b"d".to_vec()),
+        };
+        old_state.markets.insert(&market_account_id());
+        near_sdk::env::state_write(&old_state);
+
+        let contract = MarketFactory::migrate(bob());
+
+        assert_eq!(contract.get_markets_list(), vec![market_account_id()]);
+        assert_eq!(contract.get_owner(), bob());
+        assert_eq!(contract.get_creation_fee(), U128(0));
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+
+        contract.propose_owner(Some(bob()));
+        assert_eq!(contract.get_owner(), alice());
+        assert_eq!(contract.get_proposed_owner(), Some(bob()));
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        contract.accept_ownership();
+
+        assert_eq!(contract.get_owner(), bob());
+        assert_eq!(contract.get_proposed_owner(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FACTORY_IS_PAUSED")]
+    fn create_market_error_if_factory_is_paused() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+
+        contract.grant_role(carol(), Role::Pauser);
+        assert!(contract.has_role(carol(), Role::Pauser));
+        assert!(!contract.has_role(carol(), Role::FeeManager));
+
+        testing_env!(context.predecessor_account_id(carol()).build());
+        contract.pause();
+
+        testing_env!(context.predecessor_account_id(alice()).build());
+        call_create_market(&mut contract, create_market_args());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PREDECESSOR_IS_MISSING_ROLE")]
+    fn pause_error_if_predecessor_is_missing_role() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+
+        contract.grant_role(carol(), Role::Pauser);
+        contract.revoke_role(carol(), Role::Pauser);
+
+        testing_env!(context.predecessor_account_id(carol()).build());
+        contract.pause();
+    }
+
+    #[test]
+    fn create_market_charges_creation_fee() {
+        let context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.set_creation_fee(U128(STORAGE_DEPOSIT_BOND / 2));
+
+        call_create_market(&mut contract, create_market_args());
+
+        let pending_creation = contract.get_pending_creation(market_account_id()).unwrap();
+        assert_eq!(
+            pending_creation.creation_fee,
+            U128(STORAGE_DEPOSIT_BOND / 2)
+        );
+        assert_eq!(contract.get_collected_creation_fees(), U128(0));
+
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        assert_eq!(
+            contract.get_collected_creation_fees(),
+            U128(STORAGE_DEPOSIT_BOND / 2)
+        );
+    }
+
+    #[test]
+    fn create_market_uses_ft_creation_fee_credit() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.set_creation_fee(U128(STORAGE_DEPOSIT_BOND * 10));
+        contract.set_ft_creation_fee(collateral_token_account_id(), Some(U128(10_000_000)));
+
+        testing_env!(context
+            .predecessor_account_id(collateral_token_account_id())
+            .build());
+        let unused_amount = match contract.ft_on_transfer(alice(), U128(25_000_000), "".to_string())
+        {
+            PromiseOrValue::Value(amount) => amount,
+            PromiseOrValue::Promise(_) => panic!("expected a value"),
+        };
+
+        assert_eq!(unused_amount, U128(5_000_000));
+        assert_eq!(contract.get_creation_fee_credits(alice()), 2);
+        assert_eq!(
+            contract.get_collected_ft_creation_fees(collateral_token_account_id()),
+            U128(20_000_000)
+        );
+
+        testing_env!(context.predecessor_account_id(alice()).build());
+        call_create_market(&mut contract, create_market_args());
+
+        let pending_creation = contract.get_pending_creation(market_account_id()).unwrap();
+        assert_eq!(pending_creation.creation_fee, U128(0));
+        assert!(pending_creation.is_fee_credit_used);
+        assert_eq!(contract.get_creation_fee_credits(alice()), 1);
+
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        contract.on_create_market_callback(market_account_id(), collateral_token_account_id());
+
+        assert_eq!(contract.get_creation_fee_credits(alice()), 2);
+
+        // No fee is owed, the credits are kept
+        testing_env!(context.predecessor_account_id(alice()).build());
+        contract.set_creation_fee(U128(0));
+        call_create_market(&mut contract, create_market_args());
+
+        let pending_creation = contract.get_pending_creation(market_account_id()).unwrap();
+        assert!(!pending_creation.is_fee_credit_used);
+        assert_eq!(contract.get_creation_fee_credits(alice()), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_COLLATERAL_TOKEN_NOT_ALLOWED")]
+    fn create_market_error_if_collateral_token_is_not_allowed() {
+        setup_context();
+
+        let mut contract = setup_contract();
+        contract.remove_collateral_token(collateral_token_account_id());
+
+        call_create_market(&mut contract, create_market_args());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_DAO_NOT_ALLOWED")]
+    fn create_market_error_if_dao_is_not_allowed() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        let mut args = create_market_args();
+        args["management"]["dao_account_id"] = json!(alice());
+
+        call_create_market(&mut contract, args);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_COLLATERAL_TOKEN_DECIMALS_MISMATCH")]
+    fn create_market_error_if_collateral_token_decimals_mismatch() {
+        setup_context();
+
+        let mut contract = setup_contract();
+
+        let mut args = create_market_args();
+        args["collateral_token"]["decimals"] = json!(18);
+
+        call_create_market(&mut contract, args);
+    }
+
+    #[test]
+    fn creator_bond_is_returned_after_resolution() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.set_creator_bond(U128(STORAGE_DEPOSIT_BOND / 2));
+
+        call_create_market(&mut contract, create_market_args());
+
+        let pending_creation = contract.get_pending_creation(market_account_id()).unwrap();
+        assert_eq!(
+            pending_creation.creator_bond.unwrap().amount,
+            U128(STORAGE_DEPOSIT_BOND / 2)
+        );
+
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        let creator_bond = contract
+            .get_market_creator_bond(market_account_id())
+            .unwrap();
+        assert_eq!(creator_bond.creator_account_id, alice());
+
+        testing_env!(context.predecessor_account_id(market_account_id()).build());
+        contract.update_market_status(MarketSummaryStatus::Resolved);
+
+        assert!(contract
+            .get_market_creator_bond(market_account_id())
+            .is_none());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("return_creator_bond")));
+    }
+
+    #[test]
+    fn creator_bond_is_slashed_if_market_is_invalidated() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.set_creator_bond(U128(STORAGE_DEPOSIT_BOND / 2));
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        call_create_market(&mut contract, create_market_args());
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        // A market removed from the index still settles its bond
+        testing_env!(context.predecessor_account_id(alice()).build());
+        contract.remove_market(market_account_id());
+
+        testing_env!(context.predecessor_account_id(market_account_id()).build());
+        contract.update_market_status(MarketSummaryStatus::Invalid);
+
+        assert!(contract
+            .get_market_creator_bond(market_account_id())
+            .is_none());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("slash_creator_bond")
+                && log.contains(r#""receiver_id":"dao-account-id.near""#)));
+    }
+
+    #[test]
+    fn creator_bond_is_returned_if_market_expired() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.set_creator_bond(U128(STORAGE_DEPOSIT_BOND / 2));
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        call_create_market(&mut contract, create_market_args());
+        complete_create_market(&mut contract, &context, market_account_id());
+
+        testing_env!(context.predecessor_account_id(market_account_id()).build());
+        contract.update_market_status(MarketSummaryStatus::Expired);
+
+        assert!(contract
+            .get_market_creator_bond(market_account_id())
+            .is_none());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("return_creator_bond")
+                && log.contains(r#""receiver_id":"bob.near""#)));
+    }
+
+    #[test]
+    fn create_next_in_series() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.create_series("btc-usd-1h".to_string(), series_template());
+
+        testing_env!(context.predecessor_account_id(bob()).build());
+        contract.create_next_in_series("btc-usd-1h".to_string());
+
+        assert!(
+            contract
+                .get_series("btc-usd-1h".to_string())
+                .unwrap()
+                .is_round_pending
+        );
+
+        testing_env!(context
+            .predecessor_account_id(near_sdk::env::current_account_id())
+            .build());
+        set_promise_results(
+            &context,
+            vec![PromiseResult::Successful(
+                json!({ "result": 24000.5, "round_open_timestamp": 1 })
+                    .to_string()
+                    .into_bytes(),
+            )],
+        );
+        assert!(contract.on_series_price_read_callback("btc-usd-1h".to_string(), bob()));
+
+        let series = contract.get_series("btc-usd-1h".to_string()).unwrap();
+        assert_eq!(series.rounds, 1);
+        assert!(!series.is_round_pending);
+
+        let pending_creation = contract
+            .get_pending_creation(series_market_account_id(0))
+            .unwrap();
+        assert_eq!(pending_creation.creator_account_id, bob());
+
+        complete_create_market(&mut contract, &context, series_market_account_id(0));
+
+        let series_markets = contract.get_series_markets("btc-usd-1h".to_string(), 0, 10);
+        assert_eq!(series_markets.len(), 1);
+        assert_eq!(
+            series_markets[0].market_account_id,
+            series_market_account_id(0)
+        );
+        assert_eq!(
+            series_markets[0].ends_at - series_markets[0].starts_at,
+            Duration::hours(1).num_nanoseconds().unwrap()
+        );
+        assert_eq!(
+            series_markets[0].base_currency_symbol,
+            Some("BTC".to_string())
+        );
+    }
+
+    #[test]
+    fn create_next_in_series_refunds_deposit_if_price_read_fails() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.create_series("btc-usd-1h".to_string(), series_template());
+        contract.create_next_in_series("btc-usd-1h".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(near_sdk::env::current_account_id())
+            .build());
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        assert!(!contract.on_series_price_read_callback("btc-usd-1h".to_string(), alice()));
+
+        let series = contract.get_series("btc-usd-1h".to_string()).unwrap();
+        assert_eq!(series.rounds, 0);
+        assert!(!series.is_round_pending);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("create_next_in_series_failure")));
+    }
+
+    #[test]
+    fn create_next_in_series_refunds_deposit_if_price_is_stale() {
+        let mut context = setup_context();
+        testing_env!(context.block_timestamp(date(Utc::now()) as u64).build());
+
+        let mut contract = setup_contract();
+        contract.create_series("btc-usd-1h".to_string(), series_template());
+        contract.create_next_in_series("btc-usd-1h".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(near_sdk::env::current_account_id())
+            .build());
+        set_promise_results(
+            &context,
+            vec![PromiseResult::Successful(
+                json!({
+                    "result": 24000.5,
+                    "round_open_timestamp": (Utc::now() - Duration::hours(1)).timestamp(),
+                })
+                .to_string()
+                .into_bytes(),
+            )],
+        );
+        assert!(!contract.on_series_price_read_callback("btc-usd-1h".to_string(), alice()));
+
+        let series = contract.get_series("btc-usd-1h".to_string()).unwrap();
+        assert_eq!(series.rounds, 0);
+        assert!(!series.is_round_pending);
+        assert!(contract
+            .get_pending_creation(series_market_account_id(0))
+            .is_none());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("ERR_SERIES_STRIKE_PRICE_IS_STALE")));
+    }
+
+    #[test]
+    fn create_next_in_series_refunds_deposit_if_collateral_token_is_removed() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.create_series("btc-usd-1h".to_string(), series_template());
+        contract.create_next_in_series("btc-usd-1h".to_string());
+        contract.remove_collateral_token(collateral_token_account_id());
+
+        testing_env!(context
+            .predecessor_account_id(near_sdk::env::current_account_id())
+            .build());
+        set_promise_results(
+            &context,
+            vec![PromiseResult::Successful(
+                json!({ "result": 24000.5, "round_open_timestamp": 1 })
+                    .to_string()
+                    .into_bytes(),
+            )],
+        );
+        assert!(!contract.on_series_price_read_callback("btc-usd-1h".to_string(), alice()));
+
+        let series = contract.get_series("btc-usd-1h".to_string()).unwrap();
+        assert_eq!(series.rounds, 0);
+        assert!(!series.is_round_pending);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("create_next_in_series_failure")));
+
+        testing_env!(context.predecessor_account_id(alice()).build());
+        contract.remove_series("btc-usd-1h".to_string());
+        assert!(contract.get_series("btc-usd-1h".to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CREATE_MARKET_NAME_IS_RESERVED")]
+    fn create_market_error_if_name_is_a_series_round() {
+        setup_context();
+
+        let mut contract = setup_contract();
+        contract.create_series("btc-usd-1h".to_string(), series_template());
+
+        call_create_market_with_name(&mut contract, "btc-usd-1h-0", create_market_args());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SERIES_ROUND_NOT_DUE")]
+    fn create_next_in_series_error_if_round_is_not_due() {
+        let mut context = setup_context();
+
+        let mut contract = setup_contract();
+        contract.create_series("btc-usd-1h".to_string(), series_template());
+        contract.create_next_in_series("btc-usd-1h".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(near_sdk::env::current_account_id())
+            .build());
+        set_promise_results(
+            &context,
+            vec![PromiseResult::Successful(
+                json!({ "result": 24000.5, "round_open_timestamp": 1 })
+                    .to_string()
+                    .into_bytes(),
+            )],
+        );
+        contract.on_series_price_read_callback("btc-usd-1h".to_string(), alice());
 
-        contract.create_market(name, args.to_string().into_bytes().to_vec().into());
+        testing_env!(context.predecessor_account_id(alice()).build());
+        contract.create_next_in_series("btc-usd-1h".to_string());
     }
 }
@@ -12,11 +12,44 @@ impl MarketFactory {
         self.markets.len()
     }
 
+    pub fn get_pending_creation(&self, market_account_id: AccountId) -> Option<PendingCreation> {
+        self.pending_creations.get(&market_account_id)
+    }
+
     pub fn get_markets(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
         let elements = self.markets.as_vector();
 
-        (from_index..std::cmp::min(from_index + limit, elements.len()))
+        (from_index..std::cmp::min(from_index.saturating_add(limit), elements.len()))
+            .filter_map(|index| elements.get(index))
+            .collect()
+    }
+
+    pub fn get_market_summary(&self, market_account_id: AccountId) -> Option<MarketSummary> {
+        self.market_summaries.get(&market_account_id)
+    }
+
+    /**
+     * @notice pages through the markets index and returns the ones matching every filter that is set
+     *
+     * @param filter, an empty filter matches every market
+     * @param from_index, the index of the first market to read
+     * @param limit, the max number of markets to read, a page may hold fewer matches
+     *
+     * @returns the matching market summaries
+     */
+    pub fn get_market_summaries(
+        &self,
+        filter: Option<MarketSummaryFilter>,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<MarketSummary> {
+        let filter = filter.unwrap_or_default();
+        let elements = self.markets.as_vector();
+
+        (from_index..std::cmp::min(from_index.saturating_add(limit), elements.len()))
             .filter_map(|index| elements.get(index))
+            .filter_map(|market_account_id| self.market_summaries.get(&market_account_id))
+            .filter(|market_summary| filter.matches(market_summary))
             .collect()
     }
 }
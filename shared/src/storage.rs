@@ -1,4 +1,5 @@
 use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
     serde::{Deserialize, Serialize},
     AccountId,
 };
@@ -6,7 +7,7 @@ use near_sdk::{
 pub type Price = f64;
 pub type OutcomeId = u64;
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone)]
 pub struct Ix {
     pub address: [u8; 32],
 }
@@ -20,6 +21,107 @@ pub struct AbovePriceFeedArgs {
     pub predecessor_account_id: Option<AccountId>,
 }
 
+// Oracle data the market was resolved with
+#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone)]
+pub struct ResolutionEvidence {
+    pub result: Price,
+    pub round_open_timestamp: i64,
+}
+
+// Typed arguments of MarketFactory::create_market, serialized as the args of amm::Market::new
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CreateMarketArgs {
+    pub market: MarketDataArgs,
+    pub resolution: ResolutionArgs,
+    pub management: ManagementArgs,
+    pub collateral_token: CollateralTokenArgs,
+    pub fees: FeesArgs,
+    pub price: Option<PricingArgs>,
+    pub limits: Option<LimitsArgs>,
+    pub allow_list: Option<AllowListArgs>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MarketDataArgs {
+    pub description: String,
+    pub info: String,
+    pub category: Option<String>,
+    pub options: Vec<String>,
+    pub starts_at: i64,
+    pub close_at: i64,
+    pub ends_at: i64,
+    pub utc_offset: i8,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ResolutionArgs {
+    pub window: i64,
+    pub ix: Ix,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManagementArgs {
+    pub dao_account_id: AccountId,
+    // Always overwritten with the account calling MarketFactory::create_market
+    pub market_creator_account_id: Option<AccountId>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CollateralTokenArgs {
+    pub id: AccountId,
+    pub decimals: u8,
+    #[serde(default)]
+    pub balance: u128,
+    #[serde(default)]
+    pub fee_balance: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FeesArgs {
+    pub fee_ratio: u128,
+    pub claiming_window: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PricingArgs {
+    pub value: Price,
+    pub base_currency_symbol: String,
+    pub target_currency_symbol: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LimitsArgs {
+    pub max_collateral_per_account: Option<u128>,
+    pub max_outcome_token_supply: Option<u128>,
+    pub min_bet_amount: Option<u128>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AllowListArgs {
+    pub accounts: Vec<AccountId>,
+    pub registry_account_id: Option<AccountId>,
+}
+
+// The status of a market as listed by the MarketFactory, updated by the market itself
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum MarketSummaryStatus {
+    // The market account is being created
+    Pending,
+    // The market is created and listed in MarketFactory.markets
+    Active,
+    // The market was resolved, see amm::Market::resolve
+    Resolved,
+    // The market was invalidated by its DAO, holders get their collateral back, see amm::Market::invalidate
+    Invalid,
+    // The resolution window expired unresolved and anyone invalidated the market, holders get their collateral back
+    Expired,
+    // The market account was deleted, see amm::Market::finalize
+    Finalized,
+    // The market creator deleted the market account before the first buy, see amm::Market::cancel
+    Cancelled,
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum Payload {
     AggregatorReadArgs(AbovePriceFeedArgs),